@@ -0,0 +1,33 @@
+//! Times `Request::query_params` for queries of growing size. Parsing is a single pass
+//! over the query, so the time per parameter should stay about the same as the query
+//! grows; run with `cargo bench --bench query_params`.
+
+extern crate http2;
+
+use std::time::Instant;
+
+use http2::request::Request;
+
+const ITERATIONS: u32 = 200;
+
+fn main() {
+    for &count in &[1000, 10000] {
+        let query = (0..count)
+                        .map(|i| format!("filter{}=value+{}", i, i))
+                        .collect::<Vec<_>>()
+                        .join("&");
+        let text = format!("GET /search?{} HTTP/1.1\r\n\r\n", query);
+        let request = Request::from_str(&text).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            assert_eq!(request.query_params().len(), count);
+        }
+        let elapsed = start.elapsed() / ITERATIONS;
+
+        println!("{:>6} parameters: {:>10?} per parse, {:>8?} per parameter",
+                 count,
+                 elapsed,
+                 elapsed / count as u32);
+    }
+}
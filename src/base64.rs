@@ -0,0 +1,61 @@
+/// Decodes base64 (RFC 4648 section 4). Padding is optional, anything else that isn't
+/// part of the alphabet makes the whole value invalid.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    decode(text, b'+', b'/')
+}
+
+/// Decodes the URL and filename safe variant of base64 (RFC 4648 section 5), which uses
+/// `-` and `_` instead of `+` and `/`
+pub fn decode_base64url(text: &str) -> Option<Vec<u8>> {
+    decode(text, b'-', b'_')
+}
+
+fn decode(text: &str, char_62: u8, char_63: u8) -> Option<Vec<u8>> {
+    let text = text.trim_right_matches('=').as_bytes();
+    // A single character left over can't hold a whole byte
+    if text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+
+    for &byte in text {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            _ if byte == char_62 => 62,
+            _ if byte == char_63 => 63,
+            _ => return None,
+        };
+
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            decoded.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_values() {
+        assert_eq!(decode_base64("aGVsbG8gd29ybGQ=").unwrap(), b"hello world");
+        assert_eq!(decode_base64("aGVsbG8gd29ybGQ").unwrap(), b"hello world");
+        assert_eq!(decode_base64("+/8=").unwrap(), b"\xfb\xff");
+        assert_eq!(decode_base64url("-_8").unwrap(), b"\xfb\xff");
+        assert_eq!(decode_base64url("+/8"), None);
+        assert_eq!(decode_base64("a"), None);
+        assert_eq!(decode_base64("").unwrap(), b"");
+    }
+}
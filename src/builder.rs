@@ -0,0 +1,113 @@
+use config::BuildConfig;
+use request::{is_safe_header_value, HttpVersion, Method, ParserError};
+
+/// Builds a request to send, like a client or a proxy does
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct RequestBuilder {
+    method: Method,
+    url: String,
+    version: HttpVersion,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl RequestBuilder {
+    pub fn new(method: Method, url: &str) -> RequestBuilder {
+        RequestBuilder::with_config(method, url, &BuildConfig::default())
+    }
+
+    pub fn with_config(method: Method, url: &str, config: &BuildConfig) -> RequestBuilder {
+        RequestBuilder {
+            method: method,
+            url: url.to_string(),
+            version: config.version,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn version(mut self, version: HttpVersion) -> RequestBuilder {
+        self.version = version;
+        self
+    }
+
+    /// Adds a header, keeping any earlier ones of the same name. The value is sent as it
+    /// is, so one that doesn't come from us should go through `try_header` instead.
+    pub fn header(mut self, name: &str, value: &str) -> RequestBuilder {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a header like `header`, unless the value would end the header line early
+    /// (see `is_safe_header_value`), which is a `ParserError::InvalidHeader`
+    pub fn try_header(self, name: &str, value: &str) -> Result<RequestBuilder, ParserError> {
+        if !is_safe_header_value(value) {
+            return Err(ParserError::InvalidHeader(name.to_string()));
+        }
+        Ok(self.header(name, value))
+    }
+
+    pub fn body(mut self, body: &[u8]) -> RequestBuilder {
+        self.body = body.to_vec();
+        self
+    }
+
+    /// Serializes the request into its HTTP/1 wire form, with the headers in the order
+    /// they were added. A body gets a Content-Length unless the headers already frame it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!("{} {} {}\r\n", self.method.as_str(), self.url, self.version);
+
+        for &(ref name, ref value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+
+        let framed = self.headers.iter().any(|&(ref name, _)| {
+            name.eq_ignore_ascii_case("content-length") ||
+            name.eq_ignore_ascii_case("transfer-encoding")
+        });
+        if !self.body.is_empty() && !framed {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_request() {
+        let request = RequestBuilder::new(Method::POST, "/submit")
+                          .header("Host", "example.com")
+                          .body(b"data");
+
+        assert_eq!(String::from_utf8(request.to_bytes()).unwrap(),
+                   "POST /submit HTTP/1.1\r\nHost: example.com\r\nContent-Length: 4\r\n\r\ndata");
+    }
+
+    #[test]
+    fn http_1_0() {
+        let config = BuildConfig { version: HttpVersion::new(1, 0) };
+        let request = RequestBuilder::with_config(Method::GET, "/", &config);
+        assert_eq!(request.to_bytes(), &b"GET / HTTP/1.0\r\n\r\n"[..]);
+
+        let request = RequestBuilder::new(Method::GET, "/legacy").version(HttpVersion::new(1, 0));
+        assert_eq!(request.to_bytes(), &b"GET /legacy HTTP/1.0\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn reject_unsafe_header() {
+        let request = RequestBuilder::new(Method::GET, "/").try_header("X-Name", "ok").unwrap();
+        assert_eq!(request.to_bytes(), &b"GET / HTTP/1.1\r\nX-Name: ok\r\n\r\n"[..]);
+
+        match request.try_header("X-Name", "a\r\nHost: evil") {
+            Err(ParserError::InvalidHeader(name)) => assert_eq!(name, "X-Name"),
+            other => panic!("expected InvalidHeader, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use date::HttpDate;
+use headers::{split_list_header, split_unquoted, unquote};
+use request::Request;
+use response::Response;
+
+/// One entry of a Warning header (RFC 7234 section 5.5), e.g.
+/// `110 cache.example.com "Response is Stale"`
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Warning {
+    // 1xx warnings describe the freshness of the response, 2xx its transformation
+    pub code: u16,
+    // The host (and port) or pseudonym of whoever added the warning, `-` if unknown
+    pub agent: String,
+    pub text: String,
+    pub date: Option<HttpDate>,
+}
+
+impl<'a> Request<'a> {
+    /// Whether the client is checking if its cached copy is still fresh, so a `304 Not
+    /// Modified` is the answer if it is. That's a request with If-None-Match or
+    /// If-Modified-Since, unless Cache-Control (or Pragma, without a Cache-Control) says
+    /// no-cache, which is a forced reload rather than a check.
+    pub fn is_revalidation(&self) -> bool {
+        let conditional = self.headers.contains_key("if-none-match") ||
+                          self.headers.contains_key("if-modified-since");
+
+        let no_cache = |name: &str| {
+            self.header_list(name).iter().any(|directive| {
+                let name = directive.splitn(2, '=').next().unwrap_or("");
+                name.trim().eq_ignore_ascii_case("no-cache")
+            })
+        };
+        let forced = if self.headers.contains_key("cache-control") {
+            no_cache("cache-control")
+        } else {
+            no_cache("pragma")
+        };
+
+        conditional && !forced
+    }
+}
+
+impl Response {
+    /// How many seconds the response has been cached for according to the Age header,
+    /// `None` if it is missing or not a number of seconds
+    pub fn age(&self) -> Option<u64> {
+        let age = try_opt!(self.header("age")).trim();
+
+        if age.is_empty() || !age.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        // A value too large to represent still means very old (RFC 7234 section 1.2.1)
+        Some(age.parse::<u64>().unwrap_or(u64::MAX))
+    }
+
+    /// The entries of every Warning header in order. Malformed entries are skipped.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.header_values("warning")
+            .into_iter()
+            .flat_map(split_list_header)
+            .filter_map(|entry| {
+                let parts = split_unquoted(entry, ' ', false);
+                if parts.len() < 3 || parts.len() > 4 || !parts[2].starts_with('"') {
+                    return None;
+                }
+
+                let code = try_opt!(parts[0].parse::<u16>().ok());
+                if parts[0].len() != 3 {
+                    return None;
+                }
+                let date = match parts.get(3) {
+                    Some(date) => Some(try_opt!(HttpDate::parse(&unquote(date)))),
+                    None => None,
+                };
+
+                Some(Warning {
+                    code: code,
+                    agent: parts[1].to_string(),
+                    text: unquote(parts[2]).into_owned(),
+                    date: date,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::StatusCode;
+
+    #[test]
+    fn revalidation() {
+        let request = Request::from_str("GET /logo.png HTTP/1.1\r\nIf-None-Match: \"v1\"\r\n\
+                                         Cache-Control: max-age=0\r\n\r\n")
+                          .unwrap();
+        assert!(request.is_revalidation());
+
+        let request = Request::from_str("GET /logo.png HTTP/1.1\r\nIf-Modified-Since: Sun, 06 \
+                                         Nov 1994 08:49:37 GMT\r\nCache-Control: \
+                                         No-Cache\r\n\r\n")
+                          .unwrap();
+        assert!(!request.is_revalidation());
+
+        let request = Request::from_str("GET /logo.png HTTP/1.1\r\nIf-None-Match: \"v1\"\r\n\
+                                         Pragma: no-cache\r\n\r\n")
+                          .unwrap();
+        assert!(!request.is_revalidation());
+
+        let request = Request::from_str("GET /logo.png HTTP/1.1\r\n\r\n").unwrap();
+        assert!(!request.is_revalidation());
+    }
+
+    #[test]
+    fn age() {
+        let mut response = Response::new(StatusCode::Ok);
+        assert_eq!(response.age(), None);
+
+        response.add_header("Age", "3600");
+        assert_eq!(response.age(), Some(3600));
+
+        response.set_header("Age", "-1");
+        assert_eq!(response.age(), None);
+    }
+
+    #[test]
+    fn warning() {
+        let mut response = Response::new(StatusCode::Ok);
+        response.add_header("Warning", "110 cache.example.com:8080 \"Response is \\\"Stale\\\"\"");
+
+        assert_eq!(response.warnings(),
+                   vec![Warning {
+                            code: 110,
+                            agent: "cache.example.com:8080".to_string(),
+                            text: "Response is \"Stale\"".to_string(),
+                            date: None,
+                        }]);
+
+        response.set_header("Warning",
+                            "112 - \"Disconnected, sorry\" \"Sun, 06 Nov 1994 08:49:37 GMT\", \
+                             oops");
+        let warnings = response.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].text, "Disconnected, sorry");
+        assert_eq!(warnings[0].date, Some(HttpDate::from_timestamp(784111777)));
+    }
+}
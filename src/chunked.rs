@@ -0,0 +1,311 @@
+use std::io::{BufRead, BufReader, Read};
+use std::str;
+
+use request::ParserError;
+
+/// Finds the end of a chunked body at the start of `bytes` without decoding it, returning
+/// its length including the last chunk and any trailers, or `None` if it isn't complete yet
+pub fn chunked_length(bytes: &[u8]) -> Result<Option<usize>, ParserError> {
+    resume_chunked_length(bytes, &mut 0)
+}
+
+// Like `chunked_length`, but skips the chunks before `resume`, which is left at the start
+// of the first chunk that isn't all there yet
+pub(crate) fn resume_chunked_length(bytes: &[u8],
+                                    resume: &mut usize)
+                                    -> Result<Option<usize>, ParserError> {
+    let mut position = *resume;
+
+    loop {
+        *resume = position;
+        let (line, line_end) = match next_line(bytes, position) {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+        let size = try!(parse_chunk_size(line));
+        position = line_end;
+
+        if size == 0 {
+            // Skip the trailers up to the empty line ending the body
+            loop {
+                let (line, line_end) = match next_line(bytes, position) {
+                    Some(line) => line,
+                    None => return Ok(None),
+                };
+                position = line_end;
+
+                if line.is_empty() {
+                    return Ok(Some(position));
+                }
+            }
+        }
+
+        // The size is the client's word, so it's compared with what's there rather than
+        // added to the position, which could overflow
+        if size > bytes.len() - position {
+            return Ok(None);
+        }
+
+        // Every chunk's data is followed by a line ending of its own
+        position += size;
+        match next_line(bytes, position) {
+            Some((b"", line_end)) => position = line_end,
+            Some(_) => return Err(ParserError::InvalidChunkedBody),
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Decodes a complete chunked body into the data of its chunks, dropping extensions and
+/// trailers
+pub fn decode_chunked(bytes: &[u8]) -> Result<Vec<u8>, ParserError> {
+    let mut decoded = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let (line, line_end) = try!(next_line(bytes, position)
+                                        .ok_or(ParserError::InvalidChunkedBody));
+        let size = try!(parse_chunk_size(line));
+
+        if size == 0 {
+            return Ok(decoded);
+        }
+        // `next_line` never goes past the end, so this can't overflow like adding would
+        if size > bytes.len() - line_end {
+            return Err(ParserError::InvalidChunkedBody);
+        }
+
+        decoded.extend_from_slice(&bytes[line_end..line_end + size]);
+        position = match next_line(bytes, line_end + size) {
+            Some((b"", next)) => next,
+            _ => return Err(ParserError::InvalidChunkedBody),
+        };
+    }
+}
+
+// Longer chunk size lines (or trailers) are treated as an attack rather than waited for
+const MAX_LINE_LENGTH: u64 = 4096;
+
+/// Decodes a chunked body while it's read from a connection, handing out the data of each
+/// chunk as soon as it has arrived, so a proxy can pass the body on without buffering all
+/// of it. Iteration ends after the last chunk and its trailers, or with the first error.
+///
+/// The reader gets buffered, so `into_inner` hands back the buffer along with it in case
+/// another request follows the body.
+pub struct ChunkedDecoder<R: Read> {
+    reader: BufReader<R>,
+    done: bool,
+
+    // The most bytes to decode before failing with `BodyTooLarge`, and how many so far
+    max_size: Option<usize>,
+    decoded: usize,
+}
+
+impl<R: Read> ChunkedDecoder<R> {
+    pub fn new(reader: R) -> ChunkedDecoder<R> {
+        ChunkedDecoder::with_buffer(BufReader::new(reader), None)
+    }
+
+    // Reads no further than the end of the body, so the reader can be used for whatever
+    // comes next. Every chunk's data is still read in one go, only the lines byte by byte.
+    pub(crate) fn unbuffered(reader: R, max_size: Option<usize>) -> ChunkedDecoder<R> {
+        ChunkedDecoder::with_buffer(BufReader::with_capacity(1, reader), max_size)
+    }
+
+    fn with_buffer(reader: BufReader<R>, max_size: Option<usize>) -> ChunkedDecoder<R> {
+        ChunkedDecoder {
+            reader: reader,
+            done: false,
+            max_size: max_size,
+            decoded: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> BufReader<R> {
+        self.reader
+    }
+
+    // The next line without its line ending, `Incomplete` if the reader ends first
+    fn read_line(&mut self) -> Result<Vec<u8>, ParserError> {
+        let mut line = Vec::new();
+        try!(self.reader.by_ref().take(MAX_LINE_LENGTH).read_until(b'\n', &mut line));
+
+        if !line.ends_with(b"\n") {
+            return Err(if line.len() as u64 == MAX_LINE_LENGTH {
+                ParserError::InvalidChunkedBody
+            } else {
+                ParserError::Incomplete
+            });
+        }
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        Ok(line)
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<Vec<u8>>, ParserError> {
+        let size = try!(parse_chunk_size(&try!(self.read_line())));
+
+        if size == 0 {
+            // Skip the trailers up to the empty line ending the body
+            while !try!(self.read_line()).is_empty() {}
+            return Ok(None);
+        }
+
+        // Refuse before reading a chunk too large, rather than after
+        self.decoded = self.decoded.saturating_add(size);
+        if self.max_size.map_or(false, |max| self.decoded > max) {
+            return Err(ParserError::BodyTooLarge);
+        }
+
+        // The size is the client's word, so the buffer only grows as the data arrives
+        let mut data = Vec::new();
+        try!(self.reader.by_ref().take(size as u64).read_to_end(&mut data));
+        if data.len() < size {
+            return Err(ParserError::Incomplete);
+        }
+
+        // Every chunk's data is followed by a line ending of its own
+        if !try!(self.read_line()).is_empty() {
+            return Err(ParserError::InvalidChunkedBody);
+        }
+        Ok(Some(data))
+    }
+}
+
+impl<R: Read> Iterator for ChunkedDecoder<R> {
+    type Item = Result<Vec<u8>, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_chunk() {
+            Ok(Some(data)) => Some(Ok(data)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The chunk size at the start of a chunk line, ignoring any chunk extensions. The size
+/// has to be hex digits only (RFC 7230 section 4.1), with whitespace allowed only before
+/// the `;` of an extension. Parsers that disagree on how to read a size are as good for
+/// request smuggling as ones that disagree on Content-Length.
+pub fn parse_chunk_size(line: &[u8]) -> Result<usize, ParserError> {
+    let line = try!(str::from_utf8(line));
+    let size = match line.find(';') {
+        Some(extensions) => line[..extensions].trim_end_matches(&[' ', '\t'][..]),
+        None => line,
+    };
+
+    if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(ParserError::InvalidChunkedBody);
+    }
+    usize::from_str_radix(size, 16).map_err(|_| ParserError::InvalidChunkedBody)
+}
+
+// The line starting at `start` without its line ending, and the position after it
+fn next_line(bytes: &[u8], start: usize) -> Option<(&[u8], usize)> {
+    let rest = if start <= bytes.len() {
+        &bytes[start..]
+    } else {
+        return None;
+    };
+    let line_end = try_opt!(rest.iter().position(|&byte| byte == b'\n'));
+
+    let line = if line_end > 0 && rest[line_end - 1] == b'\r' {
+        &rest[..line_end - 1]
+    } else {
+        &rest[..line_end]
+    };
+
+    Some((line, start + line_end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use request::Request;
+
+    #[test]
+    fn find_body_end() {
+        let body = b"4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nExpires: never\r\n\r\nnext";
+
+        assert_eq!(chunked_length(body).unwrap(), Some(body.len() - 4));
+        assert_eq!(chunked_length(&body[..20]).unwrap(), None);
+        assert!(chunked_length(b"4\r\nWikipedia\r\n").is_err());
+        assert!(chunked_length(b"x\r\n").is_err());
+    }
+
+    #[test]
+    fn huge_chunk_size() {
+        let body = b"ffffffffffffffff\r\nabc";
+        assert_eq!(chunked_length(body).unwrap(), None);
+
+        let text = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nabc";
+        match Request::from_bytes(text.as_bytes()) {
+            Err(ParserError::Incomplete) => (),
+            other => panic!("expected Incomplete, got {:?}", other),
+        }
+        assert!(!Request::from_str(text).unwrap().is_body_complete());
+    }
+
+    #[test]
+    fn strict_chunk_size() {
+        assert_eq!(parse_chunk_size(b"1A").unwrap(), 26);
+        assert_eq!(parse_chunk_size(b"4 ;ext=1").unwrap(), 4);
+
+        for malformed in &[&b"+4"[..], b" 4", b"4 ", b"\t4", b"", b"0x4", b"-4", b"4 4;x",
+                           b"10000000000000000"] {
+            assert!(parse_chunk_size(malformed).is_err(), "accepted {:?}", malformed);
+        }
+        assert!(chunked_length(b"+4\r\nWiki\r\n0\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn decode_body() {
+        let body = b"4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nExpires: never\r\n\r\n";
+
+        assert_eq!(decode_chunked(body).unwrap(), b"Wikipedia");
+        assert!(decode_chunked(b"4\r\nWi").is_err());
+        assert!(decode_chunked(b"ffffffffffffffff\r\nabc").is_err());
+    }
+
+    #[test]
+    fn stream_chunks() {
+        let body = &b"4;ext=1\r\nWiki\r\n5\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\n\
+                      Expires: never\r\n\r\nnext"[..];
+        let mut decoder = ChunkedDecoder::new(Cursor::new(body));
+
+        let chunks = decoder.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), decode_chunked(body).unwrap());
+        assert_eq!(chunks.concat(), b"Wikipedia in\r\n\r\nchunks.");
+
+        let mut rest = String::new();
+        decoder.into_inner().read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "next");
+    }
+
+    #[test]
+    fn stream_truncated_chunk() {
+        let mut decoder = ChunkedDecoder::new(Cursor::new(&b"4\r\nWiki\r\n5\r\nped"[..]));
+
+        assert_eq!(decoder.next().unwrap().unwrap(), b"Wiki");
+        match decoder.next() {
+            Some(Err(ParserError::Incomplete)) => (),
+            other => panic!("expected Incomplete, got {:?}", other),
+        }
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use request::HttpVersion;
+
+/// Options for how strict the parser is. The defaults accept anything the plain
+/// `Request::from_str` and `Request::from_bytes` always have.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ParseConfig {
+    // Reject header names that aren't tokens and header values containing control
+    // characters other than horizontal tab, which RFC 7230 doesn't allow in field-content
+    pub validate_header_values: bool,
+
+    // The longest initial line accepted, not counting its line ending. Checked before
+    // anything else so a huge request target can't make us do any work.
+    pub max_request_line_length: Option<usize>,
+
+    // Require the three parts of the initial line to be separated by exactly one space
+    // each, as RFC 7230 does, instead of any amount of spaces and tabs
+    pub strict_request_line: bool,
+
+    // Accept targets in absolute form (`GET http://example.com/ HTTP/1.1`). Only proxies
+    // need them, an origin server is better off rejecting them than having to decide
+    // between the target's host and the Host header.
+    pub allow_absolute_form: bool,
+
+    // The most bytes a single request may take up, head and body together, so a client
+    // can't make us buffer an endless body. Only applies when parsing from bytes or a
+    // reader, `Request::from_str` already has the whole request in memory.
+    pub max_total_bytes: Option<usize>,
+
+    // The most bytes of a body `Request::read_body` reads, after undoing chunked encoding,
+    // and that `Request::decode_body_with_config` decodes it to
+    pub max_body_size: Option<usize>,
+
+    // The most bytes all header names and values together may take up, not counting the
+    // colons and line endings around them
+    pub max_total_header_bytes: Option<usize>,
+
+    // Accept methods in the wrong case, like `get`, as the method they'd be in uppercase.
+    // Methods are case-sensitive, so strictly `get` is just an unsupported method.
+    pub normalize_method_case: bool,
+
+    // Let a POST say which method it stands in for with X-HTTP-Method-Override, for
+    // clients that can't send PUT or DELETE. Anyone who can make a POST can then make any
+    // other request too, so only turn this on if that's fine with everything behind it.
+    pub allow_method_override: bool,
+
+    // Accept TRACE requests. TRACE echoes the request back, cookies and all, which
+    // cross-site tracing attacks use to read what scripts aren't supposed to, so servers
+    // that don't need it often turn it off. Then it is `ParserError::MethodNotAllowed`.
+    pub allow_trace: bool,
+
+    // The most query parameters the `_with_config` query methods decode. The query is only
+    // parsed when asked for, so this is checked then rather than when parsing the request.
+    pub max_query_params: Option<usize>,
+
+    // Copy this many bytes from the start of a request that `Request::from_str_with_config`
+    // fails to parse into the error, see `ParserError::with_context`. Off by default, as
+    // most errors are just answered with a 400 and the copy would be wasted.
+    pub capture_context: Option<usize>,
+
+    // Parse UPDATE as `Method::UPDATE` like earlier versions did. It isn't an HTTP method,
+    // so by default it's `Method::UNSUPPORTED` like any other unknown one.
+    pub recognize_update_method: bool,
+
+    // The only versions accepted, others are `ParserError::UnsupportedVersion`, like for a
+    // server that speaks exactly HTTP/1.1. Any version that parses is accepted if `None`.
+    pub accepted_versions: Option<Vec<HttpVersion>>,
+
+    // Reject a Content-Length with leading zeros like `007`. It's a valid decimal number,
+    // but proxies in front of a server might not agree on what it means, and requests
+    // that are read differently along the way are what smuggling attacks are made of.
+    pub canonical_content_length: bool,
+}
+
+impl Default for ParseConfig {
+    fn default() -> ParseConfig {
+        ParseConfig {
+            validate_header_values: false,
+            max_request_line_length: None,
+            strict_request_line: false,
+            allow_absolute_form: true,
+            max_total_bytes: None,
+            max_total_header_bytes: None,
+            max_body_size: None,
+            normalize_method_case: false,
+            allow_method_override: false,
+            allow_trace: true,
+            max_query_params: None,
+            capture_context: None,
+            recognize_update_method: false,
+            accepted_versions: None,
+            canonical_content_length: false,
+        }
+    }
+}
+
+/// Defaults for requests built with `RequestBuilder`
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct BuildConfig {
+    // The version of built requests unless `RequestBuilder::version` sets another one
+    pub version: HttpVersion,
+}
+
+impl Default for BuildConfig {
+    fn default() -> BuildConfig {
+        BuildConfig { version: HttpVersion::new(1, 1) }
+    }
+}
@@ -0,0 +1,189 @@
+use response::Response;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    // Has to come with Secure, browsers ignore the cookie otherwise
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// The attributes of a Set-Cookie header (RFC 6265 section 4.1), all unset by default
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct CookieAttributes {
+    pub path: Option<String>,
+    // Without the leading dot of the old RFC 2109 style, which is ignored when parsing
+    pub domain: Option<String>,
+    // Seconds until the cookie expires, 0 to delete it right away
+    pub max_age: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<SameSite>,
+}
+
+/// A cookie as a client gets it from a Set-Cookie header
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SetCookie {
+    pub name: String,
+    pub value: String,
+    pub attributes: CookieAttributes,
+}
+
+impl SetCookie {
+    /// Formats the cookie as the value of a Set-Cookie header, the attributes that are set
+    /// in the order RFC 6265 lists them
+    pub fn to_header_value(&self) -> String {
+        let attributes = &self.attributes;
+        let mut value = format!("{}={}", self.name, self.value);
+
+        if let Some(ref path) = attributes.path {
+            value.push_str(&format!("; Path={}", path));
+        }
+        if let Some(ref domain) = attributes.domain {
+            value.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(max_age) = attributes.max_age {
+            value.push_str(&format!("; Max-Age={}", max_age));
+        }
+        if attributes.secure {
+            value.push_str("; Secure");
+        }
+        if attributes.http_only {
+            value.push_str("; HttpOnly");
+        }
+        if let Some(same_site) = attributes.same_site {
+            value.push_str(&format!("; SameSite={}", same_site.as_str()));
+        }
+
+        value
+    }
+}
+
+/// Parses a Set-Cookie header value the way RFC 6265 section 5.2 tells clients to.
+/// Unknown attributes and attributes with invalid values are ignored, and a negative
+/// Max-Age counts as 0. `None` if there is no `name=value` pair or the name is empty,
+/// which means ignoring the whole header.
+pub fn parse_set_cookie(value: &str) -> Option<SetCookie> {
+    let mut parts = value.split(';');
+    let mut pair = try_opt!(parts.next()).splitn(2, '=');
+    let name = pair.next().unwrap_or("").trim();
+    let cookie_value = try_opt!(pair.next()).trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut attributes = CookieAttributes::default();
+    for attribute in parts {
+        let mut attribute = attribute.splitn(2, '=');
+        let attribute_name = attribute.next().unwrap_or("").trim().to_ascii_lowercase();
+        let attribute_value = attribute.next().unwrap_or("").trim();
+
+        match attribute_name.as_str() {
+            "path" if attribute_value.starts_with('/') => {
+                attributes.path = Some(attribute_value.to_string());
+            }
+            "domain" if !attribute_value.is_empty() => {
+                attributes.domain = Some(attribute_value.trim_start_matches('.').to_lowercase());
+            }
+            "max-age" => {
+                if attribute_value.starts_with('-') {
+                    attributes.max_age = Some(0);
+                } else if let Ok(max_age) = attribute_value.parse() {
+                    attributes.max_age = Some(max_age);
+                }
+            }
+            "secure" => attributes.secure = true,
+            "httponly" => attributes.http_only = true,
+            "samesite" => {
+                attributes.same_site = match attribute_value.to_ascii_lowercase().as_str() {
+                    "strict" => Some(SameSite::Strict),
+                    "lax" => Some(SameSite::Lax),
+                    "none" => Some(SameSite::None),
+                    _ => attributes.same_site,
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Some(SetCookie {
+        name: name.to_string(),
+        value: cookie_value.to_string(),
+        attributes: attributes,
+    })
+}
+
+impl Response {
+    /// Adds a Set-Cookie header, keeping any other cookies the response already sets
+    pub fn set_cookie(&mut self, name: &str, value: &str, attributes: CookieAttributes) {
+        let cookie = SetCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            attributes: attributes,
+        };
+        self.add_header("Set-Cookie", &cookie.to_header_value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::StatusCode;
+
+    #[test]
+    fn build_and_parse() {
+        let attributes = CookieAttributes {
+            path: Some("/".to_string()),
+            max_age: Some(3600),
+            http_only: true,
+            same_site: Some(SameSite::Lax),
+            ..CookieAttributes::default()
+        };
+
+        let mut response = Response::new(StatusCode::Ok);
+        response.set_cookie("session", "abc123", attributes.clone());
+        response.set_cookie("theme", "dark", CookieAttributes::default());
+
+        let values = response.header_values("set-cookie");
+        assert_eq!(values,
+                   vec!["session=abc123; Path=/; Max-Age=3600; HttpOnly; SameSite=Lax",
+                        "theme=dark"]);
+
+        assert_eq!(parse_set_cookie(values[0]),
+                   Some(SetCookie {
+                       name: "session".to_string(),
+                       value: "abc123".to_string(),
+                       attributes: attributes,
+                   }));
+    }
+
+    #[test]
+    fn parse_leniently() {
+        let cookie = parse_set_cookie("id=1; domain=.Example.com; SECURE; samesite=lax; \
+                                       Max-Age=-5; Path=relative; Unknown=x")
+                         .unwrap();
+
+        assert_eq!(cookie.attributes,
+                   CookieAttributes {
+                       domain: Some("example.com".to_string()),
+                       max_age: Some(0),
+                       secure: true,
+                       same_site: Some(SameSite::Lax),
+                       ..CookieAttributes::default()
+                   });
+
+        assert_eq!(parse_set_cookie("no pair"), None);
+        assert_eq!(parse_set_cookie("=value"), None);
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAYS: [&'static str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&'static str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep",
+                                    "Oct", "Nov", "Dec"];
+
+/// A point in time as used by HTTP date headers, with one second precision
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+pub struct HttpDate {
+    // Seconds since the unix epoch
+    timestamp: u64,
+}
+
+impl HttpDate {
+    pub fn from_timestamp(timestamp: u64) -> HttpDate {
+        HttpDate { timestamp: timestamp }
+    }
+
+    pub fn now() -> HttpDate {
+        // A clock set before 1970 is too broken to be worth an error
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        HttpDate::from_timestamp(since_epoch.as_secs())
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Parses any of the three formats RFC 7231 requires recipients to accept:
+    /// IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`), the obsolete RFC 850 form
+    /// (`Sunday, 06-Nov-94 08:49:37 GMT`) and asctime (`Sun Nov  6 08:49:37 1994`)
+    pub fn parse(text: &str) -> Option<HttpDate> {
+        let parts = text.split_whitespace().collect::<Vec<_>>();
+
+        let (day, month, year, time) = match parts.len() {
+            // IMF-fixdate
+            6 if parts[5] == "GMT" => (parts[1], parts[2], parts[3], parts[4]),
+            // RFC 850
+            4 if parts[3] == "GMT" => {
+                let mut date = parts[1].split('-');
+                let day = try_opt!(date.next());
+                let month = try_opt!(date.next());
+                let year = try_opt!(date.next());
+                (day, month, year, parts[2])
+            }
+            // asctime
+            5 => (parts[2], parts[1], parts[4], parts[3]),
+            _ => return None,
+        };
+
+        let day = try_opt!(day.parse::<u64>().ok());
+        let month = try_opt!(MONTHS.iter().position(|&name| name == month)) as u64 + 1;
+        let mut year = try_opt!(year.parse::<u64>().ok());
+
+        // RFC 850 only has two digit years, which are interpreted as the closest
+        // matching year in the past 50 years (give or take)
+        if year < 100 {
+            year += if year < 70 { 2000 } else { 1900 };
+        }
+
+        let mut clock = time.split(':').map(|part| part.parse::<u64>().ok());
+        let hours = try_opt!(try_opt!(clock.next()));
+        let minutes = try_opt!(try_opt!(clock.next()));
+        let seconds = try_opt!(try_opt!(clock.next()));
+
+        // Years have four digits at most in every format, which also keeps the timestamp
+        // from overflowing
+        if year < 1970 || year > 9999 || day == 0 || day > days_in_month(year, month) ||
+           hours > 23 || minutes > 59 || seconds > 60 || clock.next().is_some() {
+            return None;
+        }
+
+        let days = days_from_civil(year, month, day);
+
+        Some(HttpDate::from_timestamp(days * 86400 + hours * 3600 + minutes * 60 + seconds))
+    }
+}
+
+/// Formats as IMF-fixdate, the one format senders must use, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.timestamp / 86400;
+        let seconds = self.timestamp % 86400;
+        let (year, month, day) = civil_from_days(days);
+
+        write!(f,
+               "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+               // The epoch was a thursday
+               DAYS[(days % 7) as usize],
+               day,
+               MONTHS[month as usize - 1],
+               year,
+               seconds / 3600,
+               seconds / 60 % 60,
+               seconds % 60)
+    }
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since the unix epoch for a proleptic gregorian date, see
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+// The inverse of `days_from_civil`, see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 -
+                       day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formats() {
+        let expected = Some(HttpDate::from_timestamp(784111777));
+
+        assert_eq!(HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT"), expected);
+        assert_eq!(HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+        assert_eq!(HttpDate::parse("Sun Nov  6 08:49:37 1994"), expected);
+        assert_eq!(HttpDate::parse("Sun, 06 Now 1994 08:49:37 GMT"), None);
+        assert_eq!(HttpDate::parse("yesterday"), None);
+    }
+
+    #[test]
+    fn reject_impossible_dates() {
+        assert_eq!(HttpDate::parse("Sun, 06 Nov 99999999999999 08:49:37 GMT"), None);
+        assert_eq!(HttpDate::parse("Sun, 06 Nov 10000 08:49:37 GMT"), None);
+        assert!(HttpDate::parse("Fri, 31 Dec 9999 23:59:59 GMT").is_some());
+
+        assert_eq!(HttpDate::parse("Sat, 31 Feb 2024 00:00:00 GMT"), None);
+        assert_eq!(HttpDate::parse("Fri, 29 Feb 2023 00:00:00 GMT"), None);
+        assert_eq!(HttpDate::parse("Thu, 31 Apr 2024 00:00:00 GMT"), None);
+        assert!(HttpDate::parse("Thu, 29 Feb 2024 00:00:00 GMT").is_some());
+        assert!(HttpDate::parse("Tue, 29 Feb 2000 00:00:00 GMT").is_some());
+        assert_eq!(HttpDate::parse("Thu, 29 Feb 2100 00:00:00 GMT"), None);
+    }
+
+    #[test]
+    fn format_imf_fixdate() {
+        assert_eq!(HttpDate::from_timestamp(784111777).to_string(),
+                   "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(HttpDate::from_timestamp(951782400).to_string(),
+                   "Tue, 29 Feb 2000 00:00:00 GMT");
+
+        let now = HttpDate::now();
+        assert_eq!(HttpDate::parse(&now.to_string()), Some(now));
+    }
+}
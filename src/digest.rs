@@ -0,0 +1,173 @@
+use base64::decode_base64;
+use headers::split_list_header;
+use request::Request;
+#[cfg(feature = "digest")]
+use chunked::decode_chunked;
+#[cfg(feature = "digest")]
+use request::ParserError;
+
+/// A hash algorithm a client can send the digest of a body in. They're ordered from
+/// weakest to strongest, MD5 being broken and only there for Content-MD5.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm for a name from the IANA Hash Algorithms for HTTP Digest Fields
+    /// registry, matched case-insensitively
+    pub fn from_name(name: &str) -> Option<DigestAlgorithm> {
+        match name.to_ascii_lowercase().as_str() {
+            "md5" => Some(DigestAlgorithm::Md5),
+            "sha-256" => Some(DigestAlgorithm::Sha256),
+            "sha-512" => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    // The length of a digest in bytes
+    fn output_length(&self) -> usize {
+        match *self {
+            DigestAlgorithm::Md5 => 16,
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha512 => 64,
+        }
+    }
+}
+
+/// The digest of a body as the client claims it to be
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Digest {
+    pub algorithm: DigestAlgorithm,
+    pub value: Vec<u8>,
+}
+
+impl<'a> Request<'a> {
+    /// The strongest digest of the body the client sent, looking at Content-Digest (RFC
+    /// 9530, `sha-256=:base64:`), then Digest (RFC 3230, `SHA-256=base64`) and then
+    /// Content-MD5. Entries with an unknown algorithm or a value that isn't a digest of
+    /// that algorithm are skipped.
+    pub fn content_digest(&self) -> Option<Digest> {
+        for name in &["content-digest", "digest"] {
+            let values = self.header_values(name);
+            let digest = values.iter()
+                               .flat_map(|value| split_list_header(value))
+                               .filter_map(parse_digest_entry)
+                               .max_by_key(|digest| digest.algorithm);
+            if digest.is_some() {
+                return digest;
+            }
+        }
+
+        let value = try_opt!(self.headers.get("content-md5"));
+        digest(DigestAlgorithm::Md5, value)
+    }
+
+    /// Whether the body matches the digest from `content_digest`. Digests are over the body
+    /// as sent but without a chunked transfer encoding, so a compressed body is compared
+    /// while still compressed. A request without a digest is an `InvalidHeader` error, and
+    /// so is one that's chunked but not validly.
+    #[cfg(feature = "digest")]
+    pub fn verify_digest(&self) -> Result<bool, ParserError> {
+        use md5::Md5;
+        use sha2::{Digest as Hasher, Sha256, Sha512};
+
+        let expected = match self.content_digest() {
+            Some(digest) => digest,
+            None => return Err(ParserError::InvalidHeader("content-digest".to_string())),
+        };
+
+        let decoded;
+        let body = if self.is_chunked() {
+            decoded = try!(decode_chunked(self.body));
+            &decoded[..]
+        } else {
+            self.body
+        };
+
+        let actual = match expected.algorithm {
+            DigestAlgorithm::Md5 => Md5::digest(body).to_vec(),
+            DigestAlgorithm::Sha256 => Sha256::digest(body).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(body).to_vec(),
+        };
+        Ok(actual == expected.value)
+    }
+}
+
+// An `algorithm=value` entry, the value either a structured field byte sequence (`:…:`)
+// or plain base64
+fn parse_digest_entry(entry: &str) -> Option<Digest> {
+    let mut parts = entry.splitn(2, '=');
+    let algorithm = try_opt!(DigestAlgorithm::from_name(parts.next().unwrap_or("").trim()));
+    let value = try_opt!(parts.next()).trim();
+
+    let value = if value.len() >= 2 && value.starts_with(':') && value.ends_with(':') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+    digest(algorithm, value)
+}
+
+fn digest(algorithm: DigestAlgorithm, value: &str) -> Option<Digest> {
+    let value = try_opt!(decode_base64(value.trim()));
+
+    if value.len() != algorithm.output_length() {
+        return None;
+    }
+    Some(Digest {
+        algorithm: algorithm,
+        value: value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA_256: &'static str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+    fn request(headers: &str) -> String {
+        format!("POST /upload HTTP/1.1\r\nContent-Length: 5\r\n{}\r\n\r\nhello", headers)
+    }
+
+    #[test]
+    fn parse_digest() {
+        let text = request(&format!("Content-Digest: sha-256=:{}:, unknown=:AAAA:, \
+                                     md5=:XUFAKrxLKna5cZ2REBfFkg==:",
+                                    SHA_256));
+        let digest = Request::from_str(&text).unwrap().content_digest().unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(&digest.value[..4], &[0x2c, 0xf2, 0x4d, 0xba]);
+
+        let text = request("Digest: MD5=XUFAKrxLKna5cZ2REBfFkg==, SHA-256=dG9vIHNob3J0");
+        let digest = Request::from_str(&text).unwrap().content_digest().unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Md5);
+
+        let text = request("Content-MD5: XUFAKrxLKna5cZ2REBfFkg==");
+        let digest = Request::from_str(&text).unwrap().content_digest().unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Md5);
+
+        let text = request("X-Other: 1");
+        assert_eq!(Request::from_str(&text).unwrap().content_digest(), None);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn verify_digest() {
+        let text = request(&format!("Content-Digest: sha-256=:{}:", SHA_256));
+        assert!(Request::from_str(&text).unwrap().verify_digest().unwrap());
+
+        let text = request("Content-MD5: XUFAKrxLKna5cZ2REBfFkg==");
+        assert!(Request::from_str(&text).unwrap().verify_digest().unwrap());
+
+        // The digest of "hellO"
+        let text = request("Digest: sha-256=BKb1X6zi9Gvowj9ifVOYJ2FYUeEHUbY+xZ220scGt3A=");
+        assert!(!Request::from_str(&text).unwrap().verify_digest().unwrap());
+
+        let text = request("X-Other: 1");
+        assert!(Request::from_str(&text).unwrap().verify_digest().is_err());
+    }
+}
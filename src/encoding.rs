@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use chunked::decode_chunked;
+use config::ParseConfig;
+use headers::split_list_header;
+use request::{ParserError, Request};
+
+/// Decodes a body, given the most bytes it may decode to
+pub type BodyDecoder = dyn Fn(&[u8], Option<usize>) -> Result<Vec<u8>, ParserError> + Send + Sync;
+
+/// The content codings bodies can be decoded from, by name. Comes with `identity`, and
+/// `gzip` and `deflate` with the `gzip` feature, others can be added with `register`.
+pub struct BodyDecoderRegistry {
+    decoders: HashMap<String, Box<BodyDecoder>>,
+}
+
+impl BodyDecoderRegistry {
+    pub fn new() -> BodyDecoderRegistry {
+        let registry = BodyDecoderRegistry { decoders: HashMap::new() }
+                           .register("identity", |body, _| Ok(body.to_vec()));
+
+        #[cfg(feature = "gzip")]
+        let registry = registry.register("gzip", gzip::decode_gzip)
+                               .register("x-gzip", gzip::decode_gzip)
+                               .register("deflate", gzip::decode_deflate);
+
+        registry
+    }
+
+    /// Adds a decoder for a coding, replacing any the registry already has for it. Names
+    /// are case-insensitive. A decoder should stop with `ParserError::BodyTooLarge` once
+    /// its output goes past the limit it's given, rather than decode a compression bomb
+    /// into memory first.
+    pub fn register<D>(mut self, name: &str, decoder: D) -> BodyDecoderRegistry
+        where D: Fn(&[u8], Option<usize>) -> Result<Vec<u8>, ParserError> + Send + Sync + 'static
+    {
+        self.decoders.insert(name.to_lowercase(), Box::new(decoder));
+        self
+    }
+
+    /// Decodes a body from one coding, failing with `ParserError::UnsupportedEncoding` if
+    /// there is no decoder for it and with `ParserError::BodyTooLarge` if it decodes to
+    /// more than `max_size` bytes
+    pub fn decode(&self,
+                  name: &str,
+                  body: &[u8],
+                  max_size: Option<usize>)
+                  -> Result<Vec<u8>, ParserError> {
+        let decoded = match self.decoders.get(&name.to_lowercase()) {
+            Some(decoder) => try!(decoder(body, max_size)),
+            None => return Err(ParserError::UnsupportedEncoding(name.to_string())),
+        };
+
+        if is_too_large(&decoded, max_size) {
+            return Err(ParserError::BodyTooLarge);
+        }
+        Ok(decoded)
+    }
+}
+
+impl Default for BodyDecoderRegistry {
+    fn default() -> BodyDecoderRegistry {
+        BodyDecoderRegistry::new()
+    }
+}
+
+impl<'a> Request<'a> {
+    /// The codings of the Content-Encoding header in the order they were applied
+    pub fn content_encodings(&self) -> Vec<&str> {
+        self.headers.get("content-encoding").map_or(Vec::new(), |value| split_list_header(value))
+    }
+
+    /// Whether the body has a content coding other than identity, so it has to be decoded,
+    /// e.g. with `decode_body_with`, before it can be used
+    pub fn is_body_compressed(&self) -> bool {
+        self.content_encodings().iter().any(|coding| !coding.eq_ignore_ascii_case("identity"))
+    }
+
+    /// The body with its chunked transfer encoding and all the codings of its
+    /// Content-Encoding undone, the last one applied first. There's no limit on how large
+    /// the decoded body gets, see `decode_body_with_config` for that.
+    pub fn decode_body_with(&self, registry: &BodyDecoderRegistry) -> Result<Vec<u8>, ParserError> {
+        self.decode_body_with_config(registry, &ParseConfig::default())
+    }
+
+    /// Like `decode_body_with`, but fails with `ParserError::BodyTooLarge` as soon as the
+    /// body decodes to more than the config's `max_body_size`
+    pub fn decode_body_with_config(&self,
+                                   registry: &BodyDecoderRegistry,
+                                   config: &ParseConfig)
+                                   -> Result<Vec<u8>, ParserError> {
+        let mut body = if self.is_chunked() {
+            try!(decode_chunked(self.body))
+        } else {
+            self.body.to_vec()
+        };
+        if is_too_large(&body, config.max_body_size) {
+            return Err(ParserError::BodyTooLarge);
+        }
+
+        for encoding in self.content_encodings().iter().rev() {
+            body = try!(registry.decode(encoding, &body, config.max_body_size));
+        }
+
+        Ok(body)
+    }
+}
+
+fn is_too_large(body: &[u8], max_size: Option<usize>) -> bool {
+    max_size.map_or(false, |max| body.len() > max)
+}
+
+#[cfg(feature = "gzip")]
+mod gzip {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use request::ParserError;
+
+    pub fn decode_gzip(body: &[u8], max_size: Option<usize>) -> Result<Vec<u8>, ParserError> {
+        read_all(GzDecoder::new(body), max_size)
+    }
+
+    // HTTP's deflate is the zlib format, not raw deflate
+    pub fn decode_deflate(body: &[u8], max_size: Option<usize>) -> Result<Vec<u8>, ParserError> {
+        read_all(ZlibDecoder::new(body), max_size)
+    }
+
+    // Reads one byte past the limit at most, which is enough to tell it was exceeded
+    fn read_all<R: Read>(reader: R, max_size: Option<usize>) -> Result<Vec<u8>, ParserError> {
+        let limit = max_size.map_or(u64::max_value(), |max| (max as u64).saturating_add(1));
+        let mut decoded = Vec::new();
+        try!(reader.take(limit)
+                   .read_to_end(&mut decoded)
+                   .map_err(|err| ParserError::InvalidEncoding(err.to_string())));
+
+        if super::is_too_large(&decoded, max_size) {
+            return Err(ParserError::BodyTooLarge);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_decoder() {
+        let registry = BodyDecoderRegistry::new().register("rot13", |body, _| {
+            Ok(body.iter()
+                   .map(|&byte| match byte {
+                       b'a'..=b'm' | b'A'..=b'M' => byte + 13,
+                       b'n'..=b'z' | b'N'..=b'Z' => byte - 13,
+                       _ => byte,
+                   })
+                   .collect())
+        });
+
+        let request = Request::from_str("POST / HTTP/1.1\r\nContent-Encoding: ROT13, identity\r\n\
+                                         Transfer-Encoding: chunked\r\n\r\n5\r\nuryyb\r\n0\r\n\r\n")
+                          .unwrap();
+        assert_eq!(request.decode_body_with(&registry).unwrap(), b"hello");
+
+        let request = Request::from_str("POST / HTTP/1.1\r\nContent-Encoding: br\r\n\
+                                         Content-Length: 2\r\n\r\nhi")
+                          .unwrap();
+        assert!(match request.decode_body_with(&registry) {
+            Err(ParserError::UnsupportedEncoding(name)) => name == "br",
+            _ => false,
+        });
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_body() {
+        use std::io::Write;
+
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"compressed body").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut text = format!("POST / HTTP/1.1\r\nContent-Encoding: gzip\r\nContent-Length: \
+                                {}\r\n\r\n",
+                               compressed.len())
+                           .into_bytes();
+        text.extend(compressed);
+
+        let (request, _) = Request::from_bytes(&text).unwrap();
+        assert_eq!(request.decode_body_with(&BodyDecoderRegistry::new()).unwrap(),
+                   b"compressed body");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_bomb() {
+        use std::io::Write;
+
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        // A MiB of zeros compresses to about a KiB
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&[0; 1 << 20]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < 4096);
+
+        let mut text = format!("POST / HTTP/1.1\r\nContent-Encoding: gzip\r\nContent-Length: \
+                                {}\r\n\r\n",
+                               compressed.len())
+                           .into_bytes();
+        text.extend(compressed);
+        let (request, _) = Request::from_bytes(&text).unwrap();
+        let registry = BodyDecoderRegistry::new();
+
+        let config = ParseConfig { max_body_size: Some(64 * 1024), ..ParseConfig::default() };
+        match request.decode_body_with_config(&registry, &config) {
+            Err(ParserError::BodyTooLarge) => {}
+            other => panic!("expected BodyTooLarge, got {:?}", other.map(|body| body.len())),
+        }
+
+        let config = ParseConfig { max_body_size: Some(1 << 20), ..ParseConfig::default() };
+        assert_eq!(request.decode_body_with_config(&registry, &config).unwrap().len(), 1 << 20);
+    }
+
+    #[test]
+    fn compressed_body() {
+        let compressed = |headers: &str| {
+            Request::from_str(&format!("POST / HTTP/1.1\r\n{}\r\n", headers))
+                .unwrap()
+                .is_body_compressed()
+        };
+
+        assert!(compressed("Content-Encoding: gzip\r\n"));
+        assert!(compressed("Content-Encoding: identity, br\r\n"));
+        assert!(!compressed("Content-Encoding: Identity\r\n"));
+        assert!(!compressed(""));
+    }
+}
@@ -0,0 +1,347 @@
+use request::ParserError;
+
+// Frame flags, see RFC 7540 section 6
+pub const END_STREAM: u8 = 0x1;
+pub const ACK: u8 = 0x1;
+pub const END_HEADERS: u8 = 0x4;
+pub const PADDED: u8 = 0x8;
+pub const PRIORITY: u8 = 0x20;
+
+const FRAME_HEADER_LENGTH: usize = 9;
+
+// Named like the frame types in the spec
+#[allow(non_camel_case_types)]
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum FrameType {
+    DATA,
+    HEADERS,
+    PRIORITY,
+    RST_STREAM,
+    SETTINGS,
+    PUSH_PROMISE,
+    PING,
+    GOAWAY,
+    WINDOW_UPDATE,
+    CONTINUATION,
+    UNKNOWN(u8),
+}
+
+impl FrameType {
+    pub fn from_u8(kind: u8) -> FrameType {
+        use self::FrameType::*;
+
+        match kind {
+            0x0 => DATA,
+            0x1 => HEADERS,
+            0x2 => PRIORITY,
+            0x3 => RST_STREAM,
+            0x4 => SETTINGS,
+            0x5 => PUSH_PROMISE,
+            0x6 => PING,
+            0x7 => GOAWAY,
+            0x8 => WINDOW_UPDATE,
+            0x9 => CONTINUATION,
+            _ => UNKNOWN(kind),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct Frame<'a> {
+    pub kind: FrameType,
+    pub flags: u8,
+    pub stream_id: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'b> Frame<'b> {
+    /// Parses the frame at the start of the buffer and returns it along with the number of
+    /// bytes it took up, or `ParserError::Incomplete` if the whole frame isn't there yet
+    pub fn parse<'a>(bytes: &'a [u8]) -> Result<(Frame<'a>, usize), ParserError> {
+        if bytes.len() < FRAME_HEADER_LENGTH {
+            return Err(ParserError::Incomplete);
+        }
+
+        let length = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+        let frame_length = FRAME_HEADER_LENGTH + length;
+
+        if bytes.len() < frame_length {
+            return Err(ParserError::Incomplete);
+        }
+
+        let frame = Frame {
+            kind: FrameType::from_u8(bytes[3]),
+            flags: bytes[4],
+            // The most significant bit is reserved and must be ignored
+            stream_id: read_u32(&bytes[5..9]) & 0x7fffffff,
+            payload: &bytes[FRAME_HEADER_LENGTH..frame_length],
+        };
+
+        Ok((frame, frame_length))
+    }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag == flag
+    }
+}
+
+/// A complete header block, reassembled from a HEADERS frame and any CONTINUATION frames
+/// following it. The fragment is ready for HPACK decoding, with padding and priority
+/// fields already stripped.
+#[derive(Eq, PartialEq, Debug)]
+pub struct HeaderBlock {
+    pub stream_id: u32,
+    // Flags of the HEADERS frame, where END_STREAM is the interesting one
+    pub flags: u8,
+    pub fragment: Vec<u8>,
+}
+
+/// Reads a HEADERS frame and the CONTINUATION frames following it up to the one with
+/// END_HEADERS set, returning the header block and the number of bytes read. Any other
+/// frame in between is a connection error.
+pub fn read_header_block(bytes: &[u8]) -> Result<(HeaderBlock, usize), ParserError> {
+    let (headers, mut consumed) = try!(Frame::parse(bytes));
+
+    if headers.kind != FrameType::HEADERS {
+        return Err(ParserError::InvalidFrame("expected a HEADERS frame".to_string()));
+    }
+
+    let mut block = HeaderBlock {
+        stream_id: headers.stream_id,
+        flags: headers.flags,
+        fragment: try!(headers_fragment(&headers)).to_vec(),
+    };
+
+    let mut end_headers = headers.has_flag(END_HEADERS);
+
+    while !end_headers {
+        let (continuation, length) = try!(Frame::parse(&bytes[consumed..]));
+
+        if continuation.kind != FrameType::CONTINUATION ||
+           continuation.stream_id != block.stream_id {
+            return Err(ParserError::InvalidFrame("header block interrupted before END_HEADERS"
+                                                     .to_string()));
+        }
+
+        block.fragment.extend_from_slice(continuation.payload);
+        end_headers = continuation.has_flag(END_HEADERS);
+        consumed += length;
+    }
+
+    Ok((block, consumed))
+}
+
+/// The payload of a GOAWAY frame (RFC 7540 section 6.8)
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct GoAway {
+    // The highest stream that was or might still be processed, later ones can be retried
+    pub last_stream_id: u32,
+    pub error_code: u32,
+    // Opaque diagnostic data, not meant to be interpreted
+    pub debug_data: Vec<u8>,
+}
+
+impl GoAway {
+    pub fn parse(payload: &[u8]) -> Result<GoAway, ParserError> {
+        if payload.len() < 8 {
+            return Err(ParserError::InvalidFrame("GOAWAY payload shorter than 8 bytes"
+                                                     .to_string()));
+        }
+
+        Ok(GoAway {
+            last_stream_id: read_u32(&payload[..4]) & 0x7fffffff,
+            error_code: read_u32(&payload[4..8]),
+            debug_data: payload[8..].to_vec(),
+        })
+    }
+}
+
+/// The payload of a PRIORITY frame (RFC 7540 section 6.3), the same fields HEADERS frames
+/// with the PRIORITY flag start with
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Priority {
+    // Whether the stream becomes the only dependency of the stream it depends on
+    pub exclusive: bool,
+    pub stream_dependency: u32,
+    // As sent, the actual weight is one more (1 to 256)
+    pub weight: u8,
+}
+
+impl Priority {
+    pub fn parse(payload: &[u8]) -> Result<Priority, ParserError> {
+        if payload.len() != 5 {
+            return Err(ParserError::InvalidFrame("PRIORITY payload isn't 5 bytes".to_string()));
+        }
+
+        let dependency = read_u32(&payload[..4]);
+
+        Ok(Priority {
+            exclusive: dependency & 0x80000000 != 0,
+            stream_dependency: dependency & 0x7fffffff,
+            weight: payload[4],
+        })
+    }
+}
+
+/// A PING frame (RFC 7540 section 6.7), which the receiver has to answer with a PING of
+/// the same data and the ACK flag set
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Ping {
+    pub ack: bool,
+    pub data: [u8; 8],
+}
+
+impl Ping {
+    pub fn parse(flags: u8, payload: &[u8]) -> Result<Ping, ParserError> {
+        if payload.len() != 8 {
+            return Err(ParserError::InvalidFrame("PING payload isn't 8 bytes".to_string()));
+        }
+
+        let mut data = [0u8; 8];
+        data.copy_from_slice(payload);
+
+        Ok(Ping {
+            ack: flags & ACK == ACK,
+            data: data,
+        })
+    }
+
+    /// The PING answering this one
+    pub fn ack(&self) -> Ping {
+        Ping {
+            ack: true,
+            data: self.data,
+        }
+    }
+}
+
+// The header block fragment of a HEADERS frame without padding and priority fields
+fn headers_fragment<'a>(frame: &Frame<'a>) -> Result<&'a [u8], ParserError> {
+    let mut payload = frame.payload;
+    let mut padding = 0;
+
+    if frame.has_flag(PADDED) {
+        if payload.is_empty() {
+            return Err(ParserError::InvalidFrame("missing pad length".to_string()));
+        }
+        padding = payload[0] as usize;
+        payload = &payload[1..];
+    }
+
+    if frame.has_flag(PRIORITY) {
+        if payload.len() < 5 {
+            return Err(ParserError::InvalidFrame("missing priority fields".to_string()));
+        }
+        payload = &payload[5..];
+    }
+
+    if padding > payload.len() {
+        return Err(ParserError::InvalidFrame("padding exceeds the payload".to_string()));
+    }
+
+    Ok(&payload[..payload.len() - padding])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let length = payload.len();
+        let mut bytes = vec![(length >> 16) as u8,
+                             (length >> 8) as u8,
+                             length as u8,
+                             kind,
+                             flags,
+                             (stream_id >> 24) as u8,
+                             (stream_id >> 16) as u8,
+                             (stream_id >> 8) as u8,
+                             stream_id as u8];
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn parse_frame() {
+        let bytes = frame(0x6, ACK, 0, b"12345678");
+        let (ping, consumed) = Frame::parse(&bytes).unwrap();
+
+        assert_eq!(ping.kind, FrameType::PING);
+        assert!(ping.has_flag(ACK));
+        assert_eq!(ping.payload, b"12345678");
+        assert_eq!(consumed, bytes.len());
+        assert!(Frame::parse(&bytes[..12]).is_err());
+    }
+
+    #[test]
+    fn continuation_frames() {
+        let mut bytes = frame(0x1, PADDED | END_STREAM, 3, b"\x02abc\x00\x00");
+        bytes.extend(frame(0x9, 0, 3, b"def"));
+        bytes.extend(frame(0x9, END_HEADERS, 3, b"ghi"));
+        bytes.extend(frame(0x0, END_STREAM, 3, b"body"));
+
+        let (block, consumed) = read_header_block(&bytes).unwrap();
+        assert_eq!(block.stream_id, 3);
+        assert_eq!(block.flags, PADDED | END_STREAM);
+        assert_eq!(block.fragment, b"abcdefghi");
+        assert_eq!(consumed, bytes.len() - 13);
+    }
+
+    #[test]
+    fn interleaved_frame() {
+        let mut bytes = frame(0x1, 0, 3, b"abc");
+        bytes.extend(frame(0x0, 0, 3, b"body"));
+        bytes.extend(frame(0x9, END_HEADERS, 3, b"def"));
+
+        assert!(read_header_block(&bytes).is_err());
+    }
+
+    #[test]
+    fn parse_goaway() {
+        let bytes = frame(0x7, 0, 0, b"\x80\x00\x00\x07\x00\x00\x00\x02too many streams");
+        let (goaway, _) = Frame::parse(&bytes).unwrap();
+
+        assert_eq!(goaway.kind, FrameType::GOAWAY);
+        assert_eq!(GoAway::parse(goaway.payload).unwrap(),
+                   GoAway {
+                       last_stream_id: 7,
+                       error_code: 2,
+                       debug_data: b"too many streams".to_vec(),
+                   });
+        assert!(GoAway::parse(b"\x00\x00\x00\x07\x00").is_err());
+    }
+
+    #[test]
+    fn parse_priority() {
+        assert_eq!(Priority::parse(b"\x80\x00\x00\x05\xff").unwrap(),
+                   Priority {
+                       exclusive: true,
+                       stream_dependency: 5,
+                       weight: 255,
+                   });
+        assert!(!Priority::parse(b"\x00\x00\x01\x00\x0f").unwrap().exclusive);
+        assert!(Priority::parse(b"\x00\x00\x00\x05").is_err());
+        assert!(Priority::parse(b"\x00\x00\x00\x05\x01\x02").is_err());
+    }
+
+    #[test]
+    fn parse_ping() {
+        let bytes = frame(0x6, 0, 0, b"12345678");
+        let (frame, _) = Frame::parse(&bytes).unwrap();
+
+        let ping = Ping::parse(frame.flags, frame.payload).unwrap();
+        assert!(!ping.ack);
+        assert_eq!(&ping.data, b"12345678");
+
+        let ack = Ping::parse(ACK, b"12345678").unwrap();
+        assert!(ack.ack);
+        assert_eq!(ping.ack(), ack);
+
+        assert!(Ping::parse(0, b"1234567").is_err());
+        assert!(Ping::parse(0, b"123456789").is_err());
+    }
+}
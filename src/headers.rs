@@ -0,0 +1,633 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use date::HttpDate;
+use request::{header_fields, HttpVersion, Request};
+
+/// Splits a comma separated header value into its trimmed elements, keeping commas inside
+/// quoted strings (`a, "b,c"` has two elements). Empty elements are skipped as RFC 7230
+/// asks. Every list-valued header should be split with this.
+pub fn split_list_header(value: &str) -> Vec<&str> {
+    split_unquoted(value, ',', false)
+}
+
+// Splits on the separator wherever it isn't inside a quoted string or, if asked for, inside
+// the angle brackets around a URI reference
+pub(crate) fn split_unquoted(value: &str, separator: char, angle_brackets: bool) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if in_quotes && c == '\\' {
+            escaped = true;
+        } else if c == '"' && !in_brackets {
+            in_quotes = !in_quotes;
+        } else if angle_brackets && !in_quotes && (c == '<' || c == '>') {
+            in_brackets = c == '<';
+        } else if c == separator && !in_quotes && !in_brackets {
+            elements.push(value[start..i].trim());
+            start = i + c.len_utf8();
+        }
+    }
+    elements.push(value[start..].trim());
+
+    elements.retain(|element| !element.is_empty());
+    elements
+}
+
+/// Removes the quotes around a quoted-string (RFC 7230 section 3.2.6) like the
+/// `filename="a\"b.txt"` of a parameter and resolves its backslash escapes. Only allocates
+/// if there are escapes, a value without quotes is returned as it is.
+pub fn unquote(value: &str) -> Cow<'_, str> {
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Cow::Borrowed(value);
+    }
+
+    let inner = &value[1..value.len() - 1];
+    if !inner.contains('\\') {
+        return Cow::Borrowed(inner);
+    }
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            // A backslash at the very end has nothing to escape and is kept
+            unquoted.push(chars.next().unwrap_or('\\'));
+        } else {
+            unquoted.push(c);
+        }
+    }
+
+    Cow::Owned(unquoted)
+}
+
+// Splits a `name=value` parameter, lowercasing the name and unquoting the value
+pub(crate) fn parse_param(param: &str) -> (String, String) {
+    let mut parts = param.splitn(2, '=');
+    let name = parts.next().unwrap_or("").trim().to_lowercase();
+    let value = unquote(parts.next().unwrap_or("").trim());
+
+    (name, value.into_owned())
+}
+
+/// One link of a Link header (RFC 8288), like `<https://api/page/2>; rel="next"`
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Link {
+    pub target: String,
+    // Parameter names are lowercased, values have their quotes removed
+    pub params: HashMap<String, String>,
+}
+
+/// Parses all the comma separated links of a Link header value, skipping any element that
+/// doesn't start with an angle-bracketed target
+pub fn parse_link_header(value: &str) -> Vec<Link> {
+    split_unquoted(value, ',', true)
+        .into_iter()
+        .filter_map(|element| {
+            let mut parts = split_unquoted(element, ';', true).into_iter();
+            let target = try_opt!(parts.next());
+
+            if !target.starts_with('<') || !target.ends_with('>') {
+                return None;
+            }
+
+            Some(Link {
+                target: target[1..target.len() - 1].to_string(),
+                params: parts.map(parse_param).collect(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ETag {
+    pub weak: bool,
+    // The opaque tag without its surrounding quotes
+    pub tag: String,
+}
+
+impl ETag {
+    /// Parses a strong (`"abc"`) or weak (`W/"abc"`) entity tag
+    pub fn parse(text: &str) -> Option<ETag> {
+        let text = text.trim();
+        let (weak, quoted) = if text.starts_with("W/") {
+            (true, &text[2..])
+        } else {
+            (false, text)
+        };
+
+        if quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+            return None;
+        }
+
+        Some(ETag {
+            weak: weak,
+            tag: quoted[1..quoted.len() - 1].to_string(),
+        })
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum IfRange {
+    ETag(ETag),
+    Date(HttpDate),
+}
+
+/// The parameters of a `Keep-Alive` header (RFC 2068 section 19.7.1.1)
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct KeepAlive {
+    // Seconds an idle connection is kept open
+    pub timeout: Option<u64>,
+    // How many more requests the connection is used for
+    pub max: Option<u64>,
+}
+
+/// The Priority header of Extensible Priorities (RFC 9218), which HTTP/2 and HTTP/3
+/// servers schedule responses by. Not to be confused with HTTP/2's own PRIORITY frame.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Priority {
+    // 0 is the most urgent, 7 the least
+    pub urgency: u8,
+    // Whether the response is useful piece by piece, like a progressive image
+    pub incremental: bool,
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority {
+            urgency: 3,
+            incremental: false,
+        }
+    }
+}
+
+impl<'a> Request<'a> {
+    /// The elements of a list-valued header like Accept, Vary or Cache-Control, split with
+    /// `split_list_header`. Empty if the header is missing.
+    pub fn header_list(&self, name: &str) -> Vec<&str> {
+        self.headers.get(name).map_or(Vec::new(), |value| split_list_header(value))
+    }
+
+    /// Every value of a header in the order they were received, where `headers` only keeps
+    /// the last one
+    pub fn header_values(&self, name: &str) -> Vec<Cow<'a, str>> {
+        header_fields(self.header_text)
+            .filter_map(|field| field.ok())
+            .filter(|&(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// The headers sorted by name, for output that doesn't depend on the order of the
+    /// `HashMap`
+    pub fn headers_sorted(&self) -> Vec<(&str, &str)> {
+        let mut headers = self.headers
+                              .iter()
+                              .map(|(name, value)| (name.as_str(), value.as_str()))
+                              .collect::<Vec<_>>();
+        headers.sort();
+        headers
+    }
+
+    /// All values of a header joined with `", "`, which RFC 7230 section 3.2.2 says means
+    /// the same as sending them separately. Set-Cookie can't be combined that way, as its
+    /// values may contain commas, so it is always `None`: use `header_values` for it.
+    ///
+    /// A header removed from or replaced in `headers` after parsing is `None` or only has
+    /// the new value.
+    pub fn combined_header(&self, name: &str) -> Option<String> {
+        let name = name.to_lowercase();
+        if name == "set-cookie" {
+            return None;
+        }
+
+        let current = try_opt!(self.headers.get(&name));
+        let values = self.header_values(&name);
+
+        if values.last().map(|value| value.as_ref()) != Some(current.as_str()) {
+            return Some(current.clone());
+        }
+
+        Some(values.join(", "))
+    }
+
+    /// The `If-Range` validator, which is either an entity tag or a date
+    pub fn if_range(&self) -> Option<IfRange> {
+        let value = try_opt!(self.headers.get("if-range")).trim();
+
+        // Dates never start with a quote, so that alone tells the two forms apart
+        if value.starts_with('"') || value.starts_with("W/") {
+            ETag::parse(value).map(IfRange::ETag)
+        } else {
+            HttpDate::parse(value).map(IfRange::Date)
+        }
+    }
+
+    /// The `timeout` and `max` of the Keep-Alive header. Parameters that are missing or
+    /// not a number are `None`, as is a missing header.
+    pub fn keep_alive_params(&self) -> Option<KeepAlive> {
+        let mut keep_alive = KeepAlive {
+            timeout: None,
+            max: None,
+        };
+
+        for element in split_list_header(try_opt!(self.headers.get("keep-alive"))) {
+            let (name, value) = parse_param(element);
+
+            match name.as_str() {
+                "timeout" => keep_alive.timeout = value.parse().ok(),
+                "max" => keep_alive.max = value.parse().ok(),
+                _ => (),
+            }
+        }
+
+        Some(keep_alive)
+    }
+
+    /// How long the connection should be kept open waiting for the next request, or
+    /// `None` if it should be closed after this one: because of `Connection: close`, or
+    /// because it's HTTP/1.0 without `Connection: keep-alive`. The timeout is the
+    /// Keep-Alive header's if that's shorter than the server's `default`, which a client
+    /// only gets to lower.
+    pub fn effective_keep_alive_timeout(&self, default: Duration) -> Option<Duration> {
+        let connection = self.header_list("connection");
+        let has_option = |option: &str| {
+            connection.iter().any(|value| value.eq_ignore_ascii_case(option))
+        };
+
+        if has_option("close") ||
+           (self.version < HttpVersion::new(1, 1) && !has_option("keep-alive")) {
+            return None;
+        }
+
+        match self.keep_alive_params().and_then(|keep_alive| keep_alive.timeout) {
+            Some(timeout) => Some(cmp::min(Duration::from_secs(timeout), default)),
+            None => Some(default),
+        }
+    }
+
+    /// The urgency and incremental flag of the Priority header. Members that are missing
+    /// or invalid keep their defaults, `u=3` and not incremental.
+    pub fn priority(&self) -> Priority {
+        let mut priority = Priority::default();
+
+        for member in self.header_list("priority") {
+            // Members may have parameters, which are meaningless for these two
+            let (name, value) = parse_param(member.split(';').next().unwrap_or(""));
+
+            match (name.as_str(), value.as_str()) {
+                ("u", urgency) => {
+                    if let Some(urgency) = urgency.parse().ok().filter(|&urgency| urgency <= 7) {
+                        priority.urgency = urgency;
+                    }
+                }
+                ("i", "") | ("i", "?1") => priority.incremental = true,
+                ("i", "?0") => priority.incremental = false,
+                _ => (),
+            }
+        }
+
+        priority
+    }
+
+    /// The `name=value` pairs of the Cookie header in the order they were sent, duplicate
+    /// names included. Pairs without a `=` are skipped.
+    pub fn cookies_iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .get("cookie")
+            .map_or("", |value| value.as_str())
+            .split(';')
+            .filter_map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let name = parts.next().unwrap_or("").trim();
+
+                parts.next().and_then(|value| if name.is_empty() {
+                    None
+                } else {
+                    Some((name, value.trim()))
+                })
+            })
+    }
+
+    /// The links of the Link header, see `parse_link_header`
+    pub fn links(&self) -> Vec<Link> {
+        self.headers.get("link").map_or(Vec::new(), |value| parse_link_header(value))
+    }
+
+    /// The `DNT` (do not track) preference, `None` if the client didn't express one
+    pub fn do_not_track(&self) -> Option<bool> {
+        self.privacy_signal("dnt")
+    }
+
+    /// The `Sec-GPC` (global privacy control) preference, `None` if the client didn't
+    /// express one
+    pub fn global_privacy_control(&self) -> Option<bool> {
+        self.privacy_signal("sec-gpc")
+    }
+
+    /// Whether the request was sent in TLS 1.3 early data (0-RTT), as signalled by a
+    /// TLS terminating proxy with `Early-Data: 1`. Early data can be replayed by an attacker.
+    pub fn is_early_data(&self) -> bool {
+        self.headers.get("early-data").map_or(false, |value| value.trim() == "1")
+    }
+
+    /// Whether the request can be processed right away, which is the case unless it was
+    /// sent in early data with a method that isn't idempotent. Otherwise the server should
+    /// answer with `425 Too Early` so the client retries after the handshake.
+    pub fn safe_for_early_data(&self) -> bool {
+        !self.is_early_data() || self.method.is_idempotent()
+    }
+
+    fn privacy_signal(&self, name: &str) -> Option<bool> {
+        match self.headers.get(name).map(|value| value.trim()) {
+            Some("1") => Some(true),
+            Some("0") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The bare media type of the Content-Type header without any parameters, lowercased.
+    /// Only allocates if the client didn't already send it in lowercase.
+    pub fn content_type_essence(&self) -> Option<Cow<'_, str>> {
+        let value = try_opt!(self.headers.get("content-type"));
+        let essence = value.splitn(2, ';').next().unwrap_or("").trim();
+
+        if essence.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            Some(Cow::Owned(essence.to_ascii_lowercase()))
+        } else {
+            Some(Cow::Borrowed(essence))
+        }
+    }
+
+    /// The token of an `Authorization: Bearer <token>` header (RFC 6750), `None` if the
+    /// header is missing, uses another scheme or has no token
+    pub fn bearer_token(&self) -> Option<&str> {
+        let value = try_opt!(self.headers.get("authorization")).trim();
+        let mut parts = value.splitn(2, |c: char| c == ' ' || c == '\t');
+
+        if !try_opt!(parts.next()).eq_ignore_ascii_case("bearer") {
+            return None;
+        }
+
+        let token = try_opt!(parts.next()).trim();
+        if token.is_empty() {
+            None
+        } else {
+            Some(token)
+        }
+    }
+
+    /// The Idempotency-Key header, trimmed, that lets a server recognize a retried
+    /// request and answer it with the response it stored for the first attempt. The
+    /// IETF draft sends it as a quoted string, the quotes are removed so both forms give
+    /// the same key. `None` if missing or empty.
+    pub fn idempotency_key(&self) -> Option<&str> {
+        let key = try_opt!(self.headers.get("idempotency-key")).trim();
+        let key = if key.len() >= 2 && key.starts_with('"') && key.ends_with('"') {
+            &key[1..key.len() - 1]
+        } else {
+            key
+        };
+
+        if key.is_empty() {
+            None
+        } else {
+            Some(key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_list() {
+        assert_eq!(split_list_header("gzip, deflate ,br"), vec!["gzip", "deflate", "br"]);
+        assert_eq!(split_list_header("a, \"b,c\", d"), vec!["a", "\"b,c\"", "d"]);
+        assert_eq!(split_list_header("\"x\\\",y\", z"), vec!["\"x\\\",y\"", "z"]);
+        assert_eq!(split_list_header(", a,,"), vec!["a"]);
+
+        let request = Request::from_str("GET / HTTP/1.1\nCache-Control: no-cache, \
+                                         private=\"a,b\"\n\n")
+                          .unwrap();
+        assert_eq!(request.header_list("cache-control"),
+                   vec!["no-cache", "private=\"a,b\""]);
+        assert!(request.header_list("vary").is_empty());
+    }
+
+    #[test]
+    fn if_range_etag() {
+        let request = Request::from_str("GET / HTTP/1.1\nIf-Range: W/\"v2\"\n\n").unwrap();
+
+        assert_eq!(request.if_range(),
+                   Some(IfRange::ETag(ETag {
+                       weak: true,
+                       tag: "v2".to_string(),
+                   })));
+    }
+
+    #[test]
+    fn if_range_date() {
+        let request = Request::from_str("GET / HTTP/1.1\nIf-Range: Sun, 06 Nov 1994 08:49:37 \
+                                         GMT\n\n")
+                          .unwrap();
+
+        assert_eq!(request.if_range(),
+                   Some(IfRange::Date(HttpDate::from_timestamp(784111777))));
+    }
+
+    #[test]
+    fn content_type_essence() {
+        let request = Request::from_str("POST / HTTP/1.1\nContent-Type: application/json; \
+                                         charset=utf-8\n\n")
+                          .unwrap();
+        assert_eq!(request.content_type_essence(),
+                   Some(Cow::Borrowed("application/json")));
+
+        let request = Request::from_str("POST / HTTP/1.1\nContent-Type: Text/HTML\n\n").unwrap();
+        assert_eq!(request.content_type_essence().unwrap(), "text/html");
+    }
+
+    #[test]
+    fn privacy_signals() {
+        let request = Request::from_str("GET / HTTP/1.1\nDNT: 1\nSec-GPC: 0\n\n").unwrap();
+        assert_eq!(request.do_not_track(), Some(true));
+        assert_eq!(request.global_privacy_control(), Some(false));
+
+        let request = Request::from_str("GET / HTTP/1.1\n\n").unwrap();
+        assert_eq!(request.do_not_track(), None);
+        assert_eq!(request.global_privacy_control(), None);
+    }
+
+    #[test]
+    fn early_data() {
+        let post = Request::from_str("POST / HTTP/1.1\nEarly-Data: 1\n\n").unwrap();
+        assert!(post.is_early_data());
+        assert!(!post.safe_for_early_data());
+
+        let get = Request::from_str("GET / HTTP/1.1\nEarly-Data: 1\n\n").unwrap();
+        assert!(get.is_early_data());
+        assert!(get.safe_for_early_data());
+
+        let post = Request::from_str("POST / HTTP/1.1\n\n").unwrap();
+        assert!(!post.is_early_data());
+        assert!(post.safe_for_early_data());
+    }
+
+    #[test]
+    fn link_header() {
+        let request = Request::from_str("GET / HTTP/1.1\nLink: <https://api/page?p=3,4>; \
+                                         rel=\"next\"; title=\"a;b\", </page/1>; REL=prev\n\n")
+                          .unwrap();
+        let links = request.links();
+
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "https://api/page?p=3,4");
+        assert_eq!(links[0].params.get("rel"), Some(&"next".to_string()));
+        assert_eq!(links[0].params.get("title"), Some(&"a;b".to_string()));
+        assert_eq!(links[1].target, "/page/1");
+        assert_eq!(links[1].params.get("rel"), Some(&"prev".to_string()));
+
+        assert!(parse_link_header("no brackets; rel=next").is_empty());
+    }
+
+    #[test]
+    fn combined_header() {
+        let request = Request::from_str("GET / HTTP/1.1\r\nAccept: text/html\r\nSet-Cookie: \
+                                         a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT\r\nAccept: \
+                                         application/json;q=0.9\r\nSet-Cookie: b=2\r\n\r\n")
+                          .unwrap();
+
+        assert_eq!(request.combined_header("Accept"),
+                   Some("text/html, application/json;q=0.9".to_string()));
+        assert_eq!(request.combined_header("set-cookie"), None);
+        assert_eq!(request.header_values("set-cookie"),
+                   vec!["a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT", "b=2"]);
+        assert_eq!(request.combined_header("x-missing"), None);
+    }
+
+    #[test]
+    fn idempotency_key() {
+        let text = "POST /charges HTTP/1.1\r\nIdempotency-Key:  8e03978e-40d5 \r\n\r\n";
+        assert_eq!(Request::from_str(text).unwrap().idempotency_key(), Some("8e03978e-40d5"));
+
+        let text = "POST /charges HTTP/1.1\r\nIdempotency-Key: \"8e03978e-40d5\"\r\n\r\n";
+        assert_eq!(Request::from_str(text).unwrap().idempotency_key(), Some("8e03978e-40d5"));
+
+        let request = Request::from_str("POST /charges HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.idempotency_key(), None);
+    }
+
+    #[test]
+    fn bearer_token() {
+        let request = |authorization: &str| {
+            let text = format!("GET / HTTP/1.1\r\nAuthorization: {}\r\n\r\n", authorization);
+            Request::from_str(&text).unwrap().bearer_token().map(str::to_string)
+        };
+
+        assert_eq!(request("Bearer mF_9.B5f-4.1JqM"), Some("mF_9.B5f-4.1JqM".to_string()));
+        assert_eq!(request("bearer   abc  "), Some("abc".to_string()));
+        assert_eq!(request("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="), None);
+        assert_eq!(request("Bearer"), None);
+        assert_eq!(Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap().bearer_token(), None);
+    }
+
+    #[test]
+    fn unquote_values() {
+        assert_eq!(unquote("\"a\\\"b.txt\""), "a\"b.txt");
+        assert_eq!(unquote("\"back\\\\slash\""), "back\\slash");
+        assert_eq!(unquote("\"plain\""), "plain");
+
+        match unquote("token") {
+            Cow::Borrowed(value) => assert_eq!(value, "token"),
+            Cow::Owned(_) => panic!("a value without quotes shouldn't be copied"),
+        }
+    }
+
+    #[test]
+    fn headers_sorted() {
+        let request = Request::from_str("GET / HTTP/1.1\r\nUser-Agent: test\r\nAccept: */*\r\n\
+                                         Host: example.com\r\n\r\n")
+                          .unwrap();
+
+        assert_eq!(request.headers_sorted(),
+                   vec![("accept", "*/*"), ("host", "example.com"), ("user-agent", "test")]);
+    }
+
+    #[test]
+    fn keep_alive_params() {
+        let request = Request::from_str("GET / HTTP/1.1\r\nKeep-Alive: timeout=5, max=1000\r\n\r\n")
+                          .unwrap();
+        assert_eq!(request.keep_alive_params(),
+                   Some(KeepAlive {
+                       timeout: Some(5),
+                       max: Some(1000),
+                   }));
+
+        let request = Request::from_str("GET / HTTP/1.1\r\nKeep-Alive: timeout=soon\r\n\r\n")
+                          .unwrap();
+        assert_eq!(request.keep_alive_params(),
+                   Some(KeepAlive {
+                       timeout: None,
+                       max: None,
+                   }));
+
+        assert_eq!(Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap().keep_alive_params(),
+                   None);
+    }
+
+    #[test]
+    fn keep_alive_timeout() {
+        let default = Duration::from_secs(60);
+        let timeout = |text: &str| {
+            Request::from_str(text).unwrap().effective_keep_alive_timeout(default)
+        };
+
+        assert_eq!(timeout("GET / HTTP/1.1\r\nConnection: Close\r\n\r\n"), None);
+        assert_eq!(timeout("GET / HTTP/1.0\r\n\r\n"), None);
+        assert_eq!(timeout("GET / HTTP/1.0\r\nConnection: keep-alive\r\nKeep-Alive: \
+                            timeout=5\r\n\r\n"),
+                   Some(Duration::from_secs(5)));
+        assert_eq!(timeout("GET / HTTP/1.1\r\nKeep-Alive: timeout=600\r\n\r\n"),
+                   Some(default));
+        assert_eq!(timeout("GET / HTTP/1.1\r\n\r\n"), Some(default));
+    }
+
+    #[test]
+    fn priority() {
+        let request = Request::from_str("GET / HTTP/1.1\r\nPriority: u=1, i\r\n\r\n").unwrap();
+        assert_eq!(request.priority(),
+                   Priority {
+                       urgency: 1,
+                       incremental: true,
+                   });
+
+        let request = Request::from_str("GET / HTTP/1.1\r\nPriority: u=9, i=?0\r\n\r\n")
+                          .unwrap();
+        assert_eq!(request.priority(), Priority::default());
+
+        let request = Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.priority().urgency, 3);
+    }
+
+    #[test]
+    fn cookies_iter() {
+        let request = Request::from_str("GET / HTTP/1.1\r\nCookie: id=1; theme=dark;id=2 ; flag\r\n\
+                                         \r\n")
+                          .unwrap();
+
+        assert_eq!(request.cookies_iter().collect::<Vec<_>>(),
+                   vec![("id", "1"), ("theme", "dark"), ("id", "2")]);
+        assert_eq!(Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap().cookies_iter().count(),
+                   0);
+    }
+}
@@ -0,0 +1,296 @@
+use std::str;
+
+use chunked::resume_chunked_length;
+use config::ParseConfig;
+use request::{check_request_line_length, head_length, parse_head, ParserError, Request};
+
+#[derive(Debug)]
+pub enum ParseStep<'a> {
+    NeedMore,
+    Complete(Request<'a>),
+    Error(ParserError),
+}
+
+/// Buffers bytes as they are read from a (possibly non-blocking) connection until they
+/// hold a complete request. Bytes following a complete request are kept for the next one,
+/// so pipelined requests come out one at a time.
+#[derive(Debug)]
+pub struct ParseState {
+    buffer: Vec<u8>,
+    // The length of the request completed by the last call, dropped on the next one
+    consumed: usize,
+    progress: ParseProgress,
+    config: ParseConfig,
+}
+
+impl ParseState {
+    pub fn new() -> ParseState {
+        ParseState::with_config(ParseConfig::default())
+    }
+
+    pub fn with_config(config: ParseConfig) -> ParseState {
+        ParseState {
+            buffer: Vec::new(),
+            consumed: 0,
+            progress: ParseProgress::default(),
+            config: config,
+        }
+    }
+
+    /// Adds newly read bytes and tries to parse a request from everything buffered so far.
+    /// Passing no bytes checks whether the buffer already holds another pipelined request.
+    pub fn parse_more<'a>(&'a mut self, bytes: &[u8]) -> ParseStep<'a> {
+        if self.consumed > 0 {
+            self.buffer.drain(..self.consumed);
+            self.consumed = 0;
+            self.progress = ParseProgress::default();
+        }
+
+        self.buffer.extend_from_slice(bytes);
+
+        match self.progress.parse(&self.buffer, &self.config) {
+            Ok((request, consumed)) => {
+                self.consumed = consumed;
+                ParseStep::Complete(request)
+            }
+            Err(ParserError::Incomplete) => ParseStep::NeedMore,
+            Err(err) => ParseStep::Error(err),
+        }
+    }
+
+    /// The bytes buffered but not yet returned as part of a request
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.consumed..]
+    }
+}
+
+impl Default for ParseState {
+    fn default() -> ParseState {
+        ParseState::new()
+    }
+}
+
+/// How far a request still arriving at the start of a growing buffer has been looked at,
+/// so that parsing it again after every read doesn't start over from its first byte. It
+/// belongs to one request in one buffer and has to be reset for the next request.
+#[derive(Debug, Default)]
+pub(crate) struct ParseProgress {
+    // Where the search for the end of the head picks up again
+    head_search: usize,
+    framing: Option<Framing>,
+}
+
+#[derive(Debug)]
+enum Framing {
+    // The length of the whole request, going by its Content-Length
+    Length(usize),
+    // Where the head ends, and where in the body the first chunk not all there yet starts
+    Chunked(usize, usize),
+}
+
+impl ParseProgress {
+    /// Parses a request like `Request::from_bytes_with_config`, but only once the bytes
+    /// added since the last call can have completed it
+    pub(crate) fn parse<'a>(&mut self,
+                            bytes: &'a [u8],
+                            config: &ParseConfig)
+                            -> Result<(Request<'a>, usize), ParserError> {
+        if try!(self.is_incomplete(bytes, config)) {
+            let too_large = config.max_total_bytes.map_or(false, |max| bytes.len() > max);
+            return Err(if too_large {
+                ParserError::BodyTooLarge
+            } else {
+                ParserError::Incomplete
+            });
+        }
+
+        Request::from_bytes_with_config(bytes, config)
+    }
+
+    // Whether the request certainly isn't complete yet. Errors are left to the full parse,
+    // which also runs right after the head arrived, to check the head and its framing.
+    fn is_incomplete(&mut self, bytes: &[u8], config: &ParseConfig) -> Result<bool, ParserError> {
+        match self.framing {
+            Some(Framing::Length(length)) => return Ok(bytes.len() < length),
+            Some(Framing::Chunked(head_length, ref mut resume)) => {
+                return match resume_chunked_length(&bytes[head_length..], resume) {
+                    Ok(length) => Ok(length.is_none()),
+                    Err(_) => Ok(false),
+                };
+            }
+            None => {}
+        }
+
+        // Don't wait for the rest of the head if the initial line is already too long
+        try!(check_request_line_length(bytes, config));
+
+        let head_length = match head_length(bytes, self.head_search) {
+            Some(head_length) => head_length,
+            None => {
+                // A line ending in the last two bytes may yet start the empty line
+                self.head_search = bytes.len().saturating_sub(2);
+                return Ok(true);
+            }
+        };
+
+        let request = str::from_utf8(&bytes[..head_length])
+                          .ok()
+                          .and_then(|head| parse_head(head, config).ok())
+                          .and_then(|head| Request::from_head(head, config).ok());
+        if let Some(request) = request {
+            self.framing = if request.is_chunked() {
+                Some(Framing::Chunked(head_length, 0))
+            } else if let Ok(length) = request.content_length() {
+                Some(Framing::Length(head_length.saturating_add(length.unwrap_or(0))))
+            } else {
+                None
+            };
+        }
+        Ok(false)
+    }
+}
+
+/// Iterates over the requests stored back to back in a buffer, like a capture of recorded
+/// traffic, borrowing each one from it. A request that fails to parse, or is cut off at
+/// the end of the buffer, is the last item.
+pub struct RequestIter<'a> {
+    bytes: &'a [u8],
+    config: ParseConfig,
+    failed: bool,
+}
+
+impl<'a> RequestIter<'a> {
+    pub fn new(bytes: &'a [u8]) -> RequestIter<'a> {
+        RequestIter::with_config(bytes, ParseConfig::default())
+    }
+
+    pub fn with_config(bytes: &'a [u8], config: ParseConfig) -> RequestIter<'a> {
+        RequestIter {
+            bytes: bytes,
+            config: config,
+            failed: false,
+        }
+    }
+
+    /// The bytes after the requests returned so far
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> Iterator for RequestIter<'a> {
+    type Item = Result<Request<'a>, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.bytes.is_empty() {
+            return None;
+        }
+
+        match Request::from_bytes_with_config(self.bytes, &self.config) {
+            Ok((request, consumed)) => {
+                self.bytes = &self.bytes[consumed..];
+                Some(Ok(request))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'b> Request<'b> {
+    /// Parses the first `n` requests stored back to back in a buffer, or all of them if
+    /// there are fewer, without looking at anything after the `n`th. Fails if any of them
+    /// doesn't parse.
+    pub fn parse_n<'a>(input: &'a [u8], n: usize) -> Result<Vec<Request<'a>>, ParserError> {
+        RequestIter::new(input).take(n).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use request::Method;
+
+    #[test]
+    fn one_byte_at_a_time() {
+        let raw = b"POST /upload HTTP/1.1\r\nContent-Length: 4\r\n\r\ndataGET";
+        let mut state = ParseState::new();
+        let mut completed = 0;
+
+        for byte in raw.iter() {
+            match state.parse_more(&[*byte]) {
+                ParseStep::NeedMore => {}
+                ParseStep::Complete(request) => {
+                    assert_eq!(request.method, Method::POST);
+                    assert_eq!(request.body, b"data");
+                    completed += 1;
+                }
+                ParseStep::Error(err) => panic!("unexpected error {:?}", err),
+            }
+        }
+
+        assert_eq!(completed, 1);
+        // The start of the next pipelined request stays buffered
+        assert_eq!(state.buffered(), b"GET");
+    }
+
+    #[test]
+    fn pipelined_one_byte_at_a_time() {
+        // Each request is only parsed in full once it can be complete, which has to give
+        // the same requests as parsing everything at once
+        let raw = b"PUT /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5;x=1\r\n\
+                    pedia\r\n0\r\nExpires: never\r\n\r\nGET /b HTTP/1.1\r\n\nPOST /c HTTP/1.1\n\
+                    Content-Length: 3\n\nabc";
+        let mut state = ParseState::new();
+        let mut requests = Vec::new();
+
+        for byte in raw.iter() {
+            match state.parse_more(&[*byte]) {
+                ParseStep::NeedMore => {}
+                ParseStep::Complete(request) => {
+                    requests.push((request.url.to_string(), request.body.len()))
+                }
+                ParseStep::Error(err) => panic!("unexpected error {:?}", err),
+            }
+        }
+
+        assert_eq!(requests,
+                   vec![("/a".to_string(), 44), ("/b".to_string(), 0), ("/c".to_string(), 3)]);
+        assert_eq!(state.buffered(), b"");
+    }
+
+    #[test]
+    fn iterate_buffer() {
+        let capture = b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nPOST /b HTTP/1.1\r\nContent-Length: \
+                        3\r\n\r\nabcPUT /c HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\nz\r\n\
+                        0\r\n\r\n";
+        let requests = RequestIter::new(capture).collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(requests.iter().map(|request| request.url).collect::<Vec<_>>(),
+                   vec!["/a", "/b", "/c"]);
+        assert_eq!(requests[1].body, b"abc");
+        assert_eq!(requests[2].body, b"1\r\nz\r\n0\r\n\r\n");
+
+        let mut truncated = RequestIter::new(b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n");
+        assert!(truncated.next().unwrap().is_ok());
+        assert!(match truncated.next() {
+            Some(Err(ParserError::Incomplete)) => true,
+            _ => false,
+        });
+        assert!(truncated.next().is_none());
+        assert_eq!(truncated.remaining(), b"GET /b HTTP/1.1\r\n");
+    }
+
+    #[test]
+    fn parse_n() {
+        let capture = b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\nGET /c HTTP/1.1\r\n\r\n";
+
+        let requests = Request::parse_n(capture, 2).unwrap();
+        assert_eq!(requests.iter().map(|request| request.url).collect::<Vec<_>>(),
+                   vec!["/a", "/b"]);
+        assert_eq!(Request::parse_n(capture, 5).unwrap().len(), 3);
+        assert!(Request::parse_n(b"GET /a HTTP/1.1\r\n", 1).is_err());
+    }
+}
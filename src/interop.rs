@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use http;
+
+use config::ParseConfig;
+use owned::OwnedRequest;
+use request::{classify_method_with_config, HttpVersion, Method, ParserError, Request};
+
+/// Takes over the method, target, version and headers of an `http::Request`, with the
+/// header names lowercased like the parser does. The body isn't copied, it can be any
+/// type there, so `body` is empty until it's set.
+impl<'a, B> From<&'a http::Request<B>> for OwnedRequest {
+    fn from(request: &'a http::Request<B>) -> OwnedRequest {
+        let method = Method::from(classify_method_with_config(request.method().as_str(),
+                                                              &ParseConfig::default()));
+
+        let version = match request.version() {
+            http::Version::HTTP_09 => HttpVersion::new(0, 9),
+            http::Version::HTTP_10 => HttpVersion::new(1, 0),
+            http::Version::HTTP_2 => HttpVersion::new(2, 0),
+            http::Version::HTTP_3 => HttpVersion::new(3, 0),
+            _ => HttpVersion::new(1, 1),
+        };
+
+        let mut headers = HashMap::new();
+        let mut header_text = String::new();
+        for (name, value) in request.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            header_text.push_str(&format!("{}: {}\r\n", name, value));
+            // The last of repeated headers wins, like when parsing
+            headers.insert(name.as_str().to_string(), value.into_owned());
+        }
+
+        let url = request.uri().to_string();
+        Request {
+            method: method,
+            url: &url,
+            version: version,
+            headers: headers,
+            header_text: &header_text,
+            body: &[],
+        }
+        .into_owned()
+    }
+}
+
+/// Builds an `http::Request` with the request's method, target, version, headers and
+/// body. Only the current value of each header is taken over, see `OwnedRequest::headers`.
+/// Fails with `InvalidInitialLine` for a method or target the http crate doesn't accept,
+/// `InvalidHttpVersion` for a version it doesn't know and `InvalidHeader` for a header.
+impl<'a> TryFrom<&'a OwnedRequest> for http::Request<Vec<u8>> {
+    type Error = ParserError;
+
+    fn try_from(request: &'a OwnedRequest) -> Result<http::Request<Vec<u8>>, ParserError> {
+        let invalid_line = || ParserError::InvalidInitialLine(request.url.clone());
+
+        let method = try!(http::Method::from_bytes(request.method.as_str().as_bytes())
+                              .map_err(|_| invalid_line()));
+        let uri = try!(request.url.parse::<http::Uri>().map_err(|_| invalid_line()));
+
+        let version = match request.version {
+            version if version == HttpVersion::new(0, 9) => http::Version::HTTP_09,
+            version if version == HttpVersion::new(1, 0) => http::Version::HTTP_10,
+            version if version == HttpVersion::new(1, 1) => http::Version::HTTP_11,
+            version if version == HttpVersion::new(2, 0) => http::Version::HTTP_2,
+            version if version == HttpVersion::new(3, 0) => http::Version::HTTP_3,
+            _ => return Err(ParserError::InvalidHttpVersion),
+        };
+
+        let mut converted = http::Request::new(request.body.clone());
+        *converted.method_mut() = method;
+        *converted.uri_mut() = uri;
+        *converted.version_mut() = version;
+
+        for (name, value) in &request.headers {
+            let invalid_header = || ParserError::InvalidHeader(name.clone());
+            let name = try!(http::header::HeaderName::from_bytes(name.as_bytes())
+                                .map_err(|_| invalid_header()));
+            let value = try!(http::header::HeaderValue::from_str(value)
+                                 .map_err(|_| invalid_header()));
+            converted.headers_mut().insert(name, value);
+        }
+
+        Ok(converted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let text = "POST /submit?x=1 HTTP/1.0\r\nHost: example.com\r\nContent-Length: 2\r\n\r\nhi";
+        let request = Request::from_str(text).unwrap().into_owned();
+
+        let converted = http::Request::try_from(&request).unwrap();
+        assert_eq!(converted.method(), http::Method::POST);
+        assert_eq!(converted.uri().path(), "/submit");
+        assert_eq!(converted.version(), http::Version::HTTP_10);
+        assert_eq!(converted.headers()["host"], "example.com");
+        assert_eq!(converted.body(), b"hi");
+
+        let mut back = OwnedRequest::from(&converted);
+        assert!(back.body.is_empty());
+        back.body = converted.body().clone();
+        assert_eq!(back.method, request.method);
+        assert_eq!(back.url, request.url);
+        assert_eq!(back.version, request.version);
+        assert_eq!(back.headers, request.headers);
+        assert_eq!(back.as_request().header_values("HOST"), vec!["example.com"]);
+        assert_eq!(back.to_bytes(), request.to_bytes());
+    }
+
+    #[test]
+    fn unsupported_method() {
+        let request = http::Request::builder().method("PURGE").uri("/cache").body(()).unwrap();
+        let converted = OwnedRequest::from(&request);
+
+        assert_eq!(converted.method, Method::UNSUPPORTED("PURGE".to_string()));
+        assert_eq!(converted.version, HttpVersion::new(1, 1));
+    }
+}
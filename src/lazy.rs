@@ -0,0 +1,58 @@
+use std::borrow::Cow;
+
+use config::ParseConfig;
+use request::{HttpVersion, Method, ParserError, header_fields, parse_head};
+
+/// A request whose initial line is parsed up front while headers are only looked up on
+/// demand, for callers like proxies that need one or two headers to route a request and
+/// don't want to pay for building the full header map.
+#[derive(Eq, PartialEq, Debug)]
+pub struct LazyRequest<'a> {
+    pub method: Method,
+    pub url: &'a str,
+    pub version: HttpVersion,
+
+    header_text: &'a str,
+}
+
+impl<'b> LazyRequest<'b> {
+    pub fn from_str<'a>(request_text: &'a str) -> Result<LazyRequest<'a>, ParserError> {
+        let head = try!(parse_head(request_text, &ParseConfig::default()));
+
+        Ok(LazyRequest {
+            method: head.method,
+            url: head.url,
+            version: head.version,
+            header_text: head.header_text,
+        })
+    }
+
+    /// Scans the raw header block for a header, comparing names case-insensitively. Like
+    /// `Request::headers` the last value wins if the header is repeated, and malformed
+    /// lines are skipped since they can't be the header we're looking for.
+    pub fn header(&self, name: &str) -> Option<Cow<'b, str>> {
+        header_fields(self.header_text)
+            .filter_map(|field| field.ok())
+            .filter(|&(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .last()
+            .map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_on_demand() {
+        let request = LazyRequest::from_str("GET /route HTTP/1.1\r\nUser-Agent: test\r\nHost: \
+                                             example.com\r\nbroken header\r\n\r\n")
+                          .unwrap();
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "/route");
+        // Borrowed straight from the request text, no header map was built
+        assert_eq!(request.header("host"), Some(Cow::Borrowed("example.com")));
+        assert_eq!(request.header("accept"), None);
+    }
+}
@@ -1,2 +1,54 @@
 #![feature(slice_patterns)]
+
+#[cfg(feature = "gzip")]
+extern crate flate2;
+#[cfg(feature = "http-interop")]
+extern crate http;
+#[cfg(feature = "digest")]
+extern crate md5;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "digest")]
+extern crate sha2;
+
+// Like `try!`, but for functions returning an `Option`
+macro_rules! try_opt {
+    ($expr:expr) => (match $expr {
+        Some(value) => value,
+        None => return None,
+    })
+}
+
+pub mod base64;
+pub mod builder;
+pub mod caching;
+pub mod chunked;
+pub mod config;
+pub mod cookie;
+pub mod date;
+pub mod digest;
+pub mod encoding;
+pub mod frame;
+pub mod headers;
+pub mod incremental;
+#[cfg(feature = "http-interop")]
+pub mod interop;
+pub mod lazy;
+pub mod logging;
+pub mod multipart;
+pub mod negotiation;
+pub mod owned;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod proxy;
 pub mod request;
+pub mod response;
+pub mod routing;
+pub mod server;
+pub mod signing;
+pub mod timing;
+pub mod trace;
+pub mod upgrade;
+pub mod url;
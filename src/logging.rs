@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use owned::OwnedRequest;
+use request::{HttpVersion, Method, Request};
+use url::{target_path, target_query};
+
+/// What an access log wants to know about a request, gathered in one place. Displays as
+/// a single line, `192.0.2.1:4321 "GET /a?b=c HTTP/1.1" example.com 12 "curl/8.0"`, with
+/// a `-` for anything unknown.
+#[derive(Eq, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LogFields<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub query: Option<&'a str>,
+    pub version: HttpVersion,
+    pub host: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+    // `None` unless there is a single valid Content-Length
+    pub content_length: Option<usize>,
+    // Only known for requests read from a connection, see `OwnedRequest::from_stream`
+    pub remote_addr: Option<SocketAddr>,
+}
+
+impl<'a> fmt::Display for LogFields<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.remote_addr {
+            Some(addr) => try!(write!(f, "{} ", addr)),
+            None => try!(write!(f, "- ")),
+        }
+
+        try!(write!(f, "\"{} {}", self.method, self.path));
+        if let Some(query) = self.query {
+            try!(write!(f, "?{}", query));
+        }
+        try!(write!(f, " {}\" {} ", self.version, self.host.unwrap_or("-")));
+
+        match self.content_length {
+            Some(length) => try!(write!(f, "{} ", length)),
+            None => try!(write!(f, "- ")),
+        }
+        write!(f, "\"{}\"", self.user_agent.unwrap_or("-"))
+    }
+}
+
+fn log_fields<'a>(method: &'a Method,
+                  url: &'a str,
+                  version: HttpVersion,
+                  headers: &'a HashMap<String, String>)
+                  -> LogFields<'a> {
+    let header = |name: &str| headers.get(name).map(|value| value.as_str());
+
+    LogFields {
+        method: method.as_str(),
+        path: target_path(url),
+        query: target_query(url),
+        version: version,
+        host: header("host"),
+        user_agent: header("user-agent"),
+        content_length: header("content-length").and_then(|value| value.trim().parse().ok()),
+        remote_addr: None,
+    }
+}
+
+impl<'b> Request<'b> {
+    pub fn log_fields(&self) -> LogFields<'_> {
+        log_fields(&self.method, self.url, self.version, &self.headers)
+    }
+}
+
+impl OwnedRequest {
+    /// Like `Request::log_fields`, with the address the request came from if it's known
+    pub fn log_fields(&self) -> LogFields<'_> {
+        LogFields {
+            remote_addr: self.remote_addr(),
+            ..log_fields(&self.method, &self.url, self.version, &self.headers)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &'static str = "POST /upload?dry_run=1 HTTP/1.1\r\nHost: example.com\r\n\
+                                User-Agent: curl/8.0\r\nContent-Length: 2\r\n\r\nhi";
+
+    #[test]
+    fn log_fields() {
+        let request = Request::from_str(TEXT).unwrap();
+        let fields = request.log_fields();
+
+        assert_eq!(fields.method, "POST");
+        assert_eq!(fields.query, Some("dry_run=1"));
+        assert_eq!(fields.content_length, Some(2));
+        assert_eq!(fields.to_string(),
+                   "- \"POST /upload?dry_run=1 HTTP/1.1\" example.com 2 \"curl/8.0\"");
+
+        let mut owned = request.clone().into_owned();
+        owned.set_remote_addr("192.0.2.1:4321".parse().unwrap());
+        assert_eq!(owned.log_fields().to_string(),
+                   "192.0.2.1:4321 \"POST /upload?dry_run=1 HTTP/1.1\" example.com 2 \
+                    \"curl/8.0\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        let request = Request::from_str(TEXT).unwrap();
+        let json = ::serde_json::to_string(&request.log_fields()).unwrap();
+
+        assert!(json.starts_with("{\"method\":\"POST\",\"path\":\"/upload\""));
+        assert!(json.contains("\"version\":{\"major\":1,\"minor\":1}"));
+        assert!(json.ends_with("\"remote_addr\":null}"));
+    }
+}
@@ -0,0 +1,361 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::str;
+
+use headers::split_unquoted;
+use request::{header_fields, ParserError, Request};
+
+// Longer boundary lines and part headers are treated as an attack rather than waited for
+const MAX_LINE_LENGTH: usize = 4096;
+const MAX_HEADERS_LENGTH: usize = 16 * 1024;
+
+/// Reads the parts of a `multipart/form-data` (or any other multipart) body one after
+/// another straight from a stream, so only a small window of the body is ever buffered.
+///
+/// Parts borrow the reader, so they are read with `next_part` instead of an iterator. A
+/// part that isn't read to the end is skipped when asking for the next one.
+pub struct MultipartReader<R: Read> {
+    reader: R,
+    // The line ending and dashes before the boundary, as it appears after a part's content
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    // Whether the buffer starts within content (a part's or the preamble's)
+    in_content: bool,
+    done: bool,
+}
+
+pub struct Part<'a, R: Read + 'a> {
+    // Header names are lowercased like those of a request
+    pub headers: HashMap<String, String>,
+    multipart: &'a mut MultipartReader<R>,
+}
+
+impl<R: Read> MultipartReader<R> {
+    pub fn new(reader: R, boundary: &str) -> MultipartReader<R> {
+        MultipartReader {
+            reader: reader,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            // The first boundary doesn't follow a line ending, pretending it does lets us
+            // treat everything before it like the content of a part
+            buffer: b"\r\n".to_vec(),
+            in_content: true,
+            done: false,
+        }
+    }
+
+    /// The next part, or `None` after the closing boundary. A body that ends before the
+    /// closing boundary is an `UnexpectedEof` error rather than a silently cut off part.
+    pub fn next_part(&mut self) -> io::Result<Option<Part<'_, R>>> {
+        // Skip whatever is left of the current part
+        let mut skipped = [0u8; 1024];
+        while try!(self.read_content(&mut skipped)) > 0 {}
+
+        if self.done {
+            return Ok(None);
+        }
+
+        // The rest of the boundary line tells if this was the closing boundary
+        while self.buffer.len() < 2 {
+            try!(self.fill());
+        }
+        if self.buffer.starts_with(b"--") {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let line_end = try!(self.find(b"\r\n", MAX_LINE_LENGTH));
+        self.buffer.drain(..line_end);
+
+        // The buffer now starts with the boundary line's line ending, so an empty header
+        // block directly follows it with another one
+        let headers_end = try!(self.find(b"\r\n\r\n", MAX_HEADERS_LENGTH));
+        let headers = {
+            let header_bytes = &self.buffer[2..cmp::max(2, headers_end)];
+            let header_text = try!(str::from_utf8(header_bytes).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, err)
+            }));
+            let mut headers = HashMap::new();
+
+            for field in header_fields(header_text) {
+                let (name, value) = try!(field.map_err(|err| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err))
+                }));
+                headers.insert(name.to_lowercase(), value.into_owned());
+            }
+
+            headers
+        };
+        self.buffer.drain(..headers_end + 4);
+        self.in_content = true;
+
+        Ok(Some(Part {
+            headers: headers,
+            multipart: self,
+        }))
+    }
+
+    fn read_content(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if !self.in_content {
+            return Ok(0);
+        }
+
+        loop {
+            let available = match position(&self.buffer, &self.delimiter, 0) {
+                Some(0) => {
+                    self.buffer.drain(..self.delimiter.len());
+                    self.in_content = false;
+                    return Ok(0);
+                }
+                Some(position) => position,
+                // Anything that could be the start of a delimiter has to wait for more bytes
+                None => self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+            };
+
+            if available > 0 {
+                let length = cmp::min(available, out.len());
+                out[..length].copy_from_slice(&self.buffer[..length]);
+                self.buffer.drain(..length);
+                return Ok(length);
+            }
+
+            try!(self.fill());
+        }
+    }
+
+    // Position of the pattern in the buffer, reading more until it shows up, as long as
+    // it's within `limit` bytes. Only the bytes that came in since the last search (and
+    // the few before them a match could start in) are searched again.
+    fn find(&mut self, pattern: &[u8], limit: usize) -> io::Result<usize> {
+        let mut start = 0;
+
+        loop {
+            match position(&self.buffer, pattern, start) {
+                Some(position) if position <= limit => return Ok(position),
+                Some(_) => break,
+                None if self.buffer.len() >= limit + pattern.len() => break,
+                None => {}
+            }
+
+            start = self.buffer.len().saturating_sub(pattern.len() - 1);
+            try!(self.fill());
+        }
+
+        Err(io::Error::new(io::ErrorKind::InvalidData,
+                           "multipart boundary line or part headers too long"))
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 4096];
+        let read = try!(self.reader.read(&mut chunk));
+
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                      "multipart body ended before the closing boundary"));
+        }
+
+        self.buffer.extend_from_slice(&chunk[..read]);
+        Ok(())
+    }
+}
+
+/// A part of a multipart body read as a whole by `parse_multipart`
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct BufferedPart {
+    pub headers: HashMap<String, String>,
+    pub content: Vec<u8>,
+}
+
+/// Reads every part of a multipart body that is already in memory. A body cut off before
+/// its closing boundary is `ParserError::TruncatedMultipart`, so an interrupted upload
+/// isn't mistaken for a complete one.
+pub fn parse_multipart(body: &[u8], boundary: &str) -> Result<Vec<BufferedPart>, ParserError> {
+    let mut multipart = MultipartReader::new(body, boundary);
+    let mut parts = Vec::new();
+
+    loop {
+        let part = multipart.next_part().and_then(|part| match part {
+            Some(mut part) => {
+                let mut content = Vec::new();
+                try!(part.read_to_end(&mut content));
+
+                Ok(Some(BufferedPart {
+                    headers: part.headers.clone(),
+                    content: content,
+                }))
+            }
+            None => Ok(None),
+        });
+
+        match part {
+            Ok(Some(part)) => parts.push(part),
+            Ok(None) => return Ok(parts),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(ParserError::TruncatedMultipart);
+            }
+            Err(err) => return Err(ParserError::from(err)),
+        }
+    }
+}
+
+impl<'a> Request<'a> {
+    /// The boundary parameter of a multipart Content-Type, without its quotes, to pass to
+    /// `parse_multipart` or `MultipartReader::new`. `None` for other content types and for
+    /// boundaries RFC 2046 doesn't allow: empty, longer than 70 characters, ending in a
+    /// space or with characters outside its small set.
+    pub fn multipart_boundary(&self) -> Option<&str> {
+        if !try_opt!(self.content_type_essence()).starts_with("multipart/") {
+            return None;
+        }
+
+        let content_type = try_opt!(self.headers.get("content-type"));
+        let boundary = try_opt!(split_unquoted(content_type, ';', false)
+                                    .into_iter()
+                                    .skip(1)
+                                    .filter_map(|param| {
+                                        let mut parts = param.splitn(2, '=');
+                                        match parts.next().unwrap_or("").trim() {
+                                            name if name.eq_ignore_ascii_case("boundary") => {
+                                                parts.next().map(str::trim)
+                                            }
+                                            _ => None,
+                                        }
+                                    })
+                                    .next());
+
+        // None of the characters allowed in a boundary need escaping, so the quotes are all
+        // there is to remove
+        let boundary = if boundary.len() >= 2 && boundary.starts_with('"') &&
+                          boundary.ends_with('"') {
+            &boundary[1..boundary.len() - 1]
+        } else {
+            boundary
+        };
+
+        let is_bchar = |c: char| c.is_ascii_alphanumeric() || "'()+_,-./:=? ".contains(c);
+        if boundary.is_empty() || boundary.len() > 70 || boundary.ends_with(' ') ||
+           !boundary.chars().all(is_bchar) {
+            return None;
+        }
+
+        Some(boundary)
+    }
+}
+
+impl<'a, R: Read> Read for Part<'a, R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.multipart.read_content(out)
+    }
+}
+
+// Position of the pattern in the bytes, looking from `start` on
+fn position(bytes: &[u8], pattern: &[u8], start: usize) -> Option<usize> {
+    bytes[start..]
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .map(|position| start + position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    const BODY: &'static [u8] = b"preamble\r\n--xyz\r\nContent-Disposition: form-data; \
+                                  name=\"a\"\r\n\r\nfirst value\r\n--xyz\r\nContent-Disposition: \
+                                  form-data; name=\"file\"; filename=\"f.txt\"\r\nContent-Type: \
+                                  text/plain\r\n\r\nline one\r\n--xy not yet\r\n--xyz--\r\n";
+
+    // A reader handing out a few bytes at a time, like a slow connection
+    struct Trickle<R>(R);
+
+    impl<R: Read> Read for Trickle<R> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let length = cmp::min(out.len(), 3);
+            self.0.read(&mut out[..length])
+        }
+    }
+
+    #[test]
+    fn read_parts() {
+        let mut multipart = MultipartReader::new(Trickle(Cursor::new(BODY)), "xyz");
+
+        {
+            let mut first = multipart.next_part().unwrap().unwrap();
+            assert_eq!(first.headers.get("content-disposition"),
+                       Some(&"form-data; name=\"a\"".to_string()));
+
+            let mut content = String::new();
+            first.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "first value");
+        }
+
+        {
+            let mut second = multipart.next_part().unwrap().unwrap();
+            assert_eq!(second.headers.get("content-type"), Some(&"text/plain".to_string()));
+
+            let mut content = String::new();
+            second.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "line one\r\n--xy not yet");
+        }
+
+        assert!(multipart.next_part().unwrap().is_none());
+    }
+
+    #[test]
+    fn skip_unread_part() {
+        let mut multipart = MultipartReader::new(Cursor::new(BODY), "xyz");
+
+        multipart.next_part().unwrap().unwrap();
+        let second = multipart.next_part().unwrap().unwrap();
+        assert!(second.headers.contains_key("content-type"));
+    }
+
+    #[test]
+    fn truncated_body() {
+        let parts = parse_multipart(BODY, "xyz").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].content, b"line one\r\n--xy not yet");
+
+        let truncated = &BODY[..BODY.len() - "--xyz--\r\n".len()];
+        match parse_multipart(truncated, "xyz") {
+            Err(ParserError::TruncatedMultipart) => (),
+            other => panic!("expected TruncatedMultipart, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn endless_lines() {
+        let endless = |start: &'static [u8]| {
+            let mut multipart = MultipartReader::new(start.chain(io::repeat(b'a')), "xyz");
+            match multipart.next_part() {
+                Err(ref err) if err.kind() == io::ErrorKind::InvalidData => (),
+                other => panic!("expected InvalidData, got {:?}", other.map(|part| part.is_some())),
+            }
+        };
+
+        endless(b"--xyz");
+        endless(b"--xyz\r\nContent-Type: ");
+    }
+
+    #[test]
+    fn boundary_from_content_type() {
+        let boundary = |content_type: &str| {
+            let text = format!("POST / HTTP/1.1\r\nContent-Type: {}\r\n\r\n", content_type);
+            Request::from_str(&text).unwrap().multipart_boundary().map(str::to_string)
+        };
+
+        assert_eq!(boundary("multipart/form-data; boundary=xyz"), Some("xyz".to_string()));
+        assert_eq!(boundary("Multipart/Mixed; charset=utf-8; Boundary=\"a b;c\""), None);
+        assert_eq!(boundary("multipart/mixed; boundary=\"gc0p4Jq0M:2Yt08j34c0p\""),
+                   Some("gc0p4Jq0M:2Yt08j34c0p".to_string()));
+        assert_eq!(boundary("text/plain; boundary=xyz"), None);
+        assert_eq!(boundary("multipart/form-data"), None);
+
+        let request = Request::from_str("POST / HTTP/1.1\r\nContent-Type: multipart/form-data; \
+                                         boundary=\"xyz\"\r\n\r\n")
+                          .unwrap();
+        let parts = parse_multipart(BODY, request.multipart_boundary().unwrap()).unwrap();
+        assert_eq!(parts[0].content, b"first value");
+    }
+}
@@ -0,0 +1,287 @@
+use std::cmp::Reverse;
+
+use headers::{split_list_header, split_unquoted};
+use request::Request;
+
+/// One element of a header with quality values like Accept-Encoding, e.g. `gzip;q=0.8`
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct QualityItem<'a> {
+    // The element without its parameters
+    pub value: &'a str,
+    // In thousandths so `q=0.001` can be told apart from `q=0`, 1000 if there's no `q`
+    pub quality: u16,
+}
+
+/// Parses every element of a header with quality values. Elements with a malformed `q`
+/// are skipped, a `q` that can't be trusted is better ignored than guessed at.
+pub fn parse_quality_list(value: &str) -> Vec<QualityItem<'_>> {
+    split_list_header(value)
+        .into_iter()
+        .filter_map(|element| {
+            let mut parts = split_unquoted(element, ';', false).into_iter();
+            let value = parts.next().unwrap_or("").trim();
+            let mut quality = 1000;
+
+            for param in parts {
+                let mut param = param.splitn(2, '=');
+                if param.next().unwrap_or("").trim().eq_ignore_ascii_case("q") {
+                    quality = try_opt!(parse_quality(param.next().unwrap_or("").trim()));
+                }
+            }
+
+            Some(QualityItem {
+                value: value,
+                quality: quality,
+            })
+        })
+        .collect()
+}
+
+// A qvalue (RFC 7231 section 5.3.1): 0 or 1 with up to three decimals, in thousandths
+fn parse_quality(text: &str) -> Option<u16> {
+    let mut parts = text.splitn(2, '.');
+    let whole = try_opt!(parts.next());
+    let decimals = parts.next().unwrap_or("");
+
+    if decimals.len() > 3 || !decimals.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let thousandths = format!("{:0<3}", decimals).parse::<u16>().unwrap_or(0);
+
+    match whole {
+        "0" => Some(thousandths),
+        "1" if thousandths == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+// How specific a media range is: 2 for a full type, 1 for `type/*` and 0 for `*/*`
+fn specificity(range: &str) -> u8 {
+    if range == "*/*" {
+        0
+    } else if range.ends_with("/*") {
+        1
+    } else {
+        2
+    }
+}
+
+// Whether the media range covers the media type, both lowercased
+fn range_matches(range: &str, media_type: &str) -> bool {
+    match specificity(range) {
+        0 => true,
+        1 => media_type.starts_with(&range[..range.len() - 1]),
+        _ => range == media_type,
+    }
+}
+
+impl<'a> Request<'a> {
+    /// The media ranges of the Accept header with their quality, lowercased and from the
+    /// most to the least preferred. Parameters other than `q` are ignored. Ties go to the
+    /// more specific range as RFC 7231 section 5.3.2 ranks them, a full type before
+    /// `type/*` before `*/*`, and then keep the order the client listed them in. Empty if
+    /// the header is missing, which means any media type is fine.
+    pub fn accept(&self) -> Vec<(String, f32)> {
+        let mut ranges = self.headers
+                             .get("accept")
+                             .map_or(Vec::new(), |value| parse_quality_list(value))
+                             .into_iter()
+                             .map(|item| (item.value.to_lowercase(), item.quality))
+                             .collect::<Vec<_>>();
+        ranges.sort_by_key(|&(ref range, quality)| (Reverse(quality), Reverse(specificity(range))));
+
+        ranges.into_iter()
+              .map(|(range, quality)| (range, quality as f32 / 1000.0))
+              .collect()
+    }
+
+    /// Picks the media type for the response out of those the server can produce, in the
+    /// server's order of preference. Each type gets the quality of the most specific range
+    /// of the Accept header that covers it, so `text/*;q=0.5, text/html` prefers HTML.
+    /// Between equal qualities the type covered by the more specific range wins, then the
+    /// server's order. `None` if the client accepts none of them.
+    pub fn preferred_media_type<'s>(&self, available: &[&'s str]) -> Option<&'s str> {
+        let accepted = match self.headers.get("accept") {
+            Some(value) => parse_quality_list(value),
+            None => return available.first().cloned(),
+        };
+
+        let mut best = None;
+        for media_type in available {
+            let lowercased = media_type.to_lowercase();
+
+            // The quality and specificity of the most specific range covering the type,
+            // the first listed of equally specific ones
+            let mut rank = None;
+            for item in &accepted {
+                let range = item.value.to_lowercase();
+                let range_specificity = specificity(&range);
+
+                if range_matches(&range, &lowercased) &&
+                   rank.map_or(true, |(_, specificity)| range_specificity > specificity) {
+                    rank = Some((item.quality, range_specificity));
+                }
+            }
+
+            if let Some(rank) = rank {
+                if rank.0 > 0 && best.map_or(true, |(_, best_rank)| rank > best_rank) {
+                    best = Some((*media_type, rank));
+                }
+            }
+        }
+
+        best.map(|(media_type, _)| media_type)
+    }
+
+    /// The charsets of the Accept-Charset header with their quality, lowercased and from
+    /// the most to the least preferred, `*` standing for any charset not listed. Ties keep
+    /// the order the client listed them in. Empty if the header is missing, which means
+    /// any charset is fine.
+    pub fn accept_charset(&self) -> Vec<(String, f32)> {
+        let mut charsets = self.headers
+                               .get("accept-charset")
+                               .map_or(Vec::new(), |value| parse_quality_list(value));
+        // Sorting is stable, and the exact thousandths compare better than the floats
+        charsets.sort_by_key(|item| Reverse(item.quality));
+
+        charsets.into_iter()
+                .map(|item| (item.value.to_lowercase(), item.quality as f32 / 1000.0))
+                .collect()
+    }
+
+    /// Picks the content coding for the response out of those the server supports, in
+    /// the server's order of preference, as the one the client's Accept-Encoding gives
+    /// the highest quality. `None` means sending the response as it is (identity).
+    ///
+    /// Codings the client doesn't list are only acceptable through `*`. Identity is
+    /// acceptable unless excluded with `identity;q=0` or `*;q=0`, so if it is excluded and
+    /// nothing else matches there is no acceptable response and `406 Not Acceptable` is
+    /// the right answer.
+    pub fn negotiate_compression<'s>(&self, server_supported: &[&'s str]) -> Option<&'s str> {
+        let accepted = parse_quality_list(try_opt!(self.headers.get("accept-encoding")));
+
+        let quality = |coding: &str| {
+            accepted.iter()
+                    .find(|item| item.value.eq_ignore_ascii_case(coding))
+                    .or_else(|| accepted.iter().find(|item| item.value == "*"))
+                    .map(|item| item.quality)
+                    .unwrap_or(if coding.eq_ignore_ascii_case("identity") {
+                        1
+                    } else {
+                        0
+                    })
+        };
+
+        let mut best = None;
+        for coding in server_supported {
+            let coding_quality = quality(coding);
+
+            if coding_quality > 0 && best.map_or(true, |(_, quality)| coding_quality > quality) {
+                best = Some((*coding, coding_quality));
+            }
+        }
+
+        match best {
+            Some((coding, _)) if !coding.eq_ignore_ascii_case("identity") => Some(coding),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiate(accept_encoding: &str, supported: &[&'static str]) -> Option<&'static str> {
+        let text = format!("GET / HTTP/1.1\r\nAccept-Encoding: {}\r\n\r\n", accept_encoding);
+        Request::from_str(&text).unwrap().negotiate_compression(supported)
+    }
+
+    #[test]
+    fn quality_list() {
+        assert_eq!(parse_quality_list("gzip;q=0.8, br, *;Q=0, x;q=2, deflate;q=0.025"),
+                   vec![QualityItem {
+                            value: "gzip",
+                            quality: 800,
+                        },
+                        QualityItem {
+                            value: "br",
+                            quality: 1000,
+                        },
+                        QualityItem {
+                            value: "*",
+                            quality: 0,
+                        },
+                        QualityItem {
+                            value: "deflate",
+                            quality: 25,
+                        }]);
+    }
+
+    #[test]
+    fn accept_charset() {
+        let text = "GET / HTTP/1.1\r\nAccept-Charset: iso-8859-1;q=0.5, UTF-8, *;q=0.1\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+
+        assert_eq!(request.accept_charset(),
+                   vec![("utf-8".to_string(), 1.0),
+                        ("iso-8859-1".to_string(), 0.5),
+                        ("*".to_string(), 0.1)]);
+
+        let request = Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(request.accept_charset().is_empty());
+    }
+
+    #[test]
+    fn accept_specificity() {
+        let text = "GET / HTTP/1.1\r\nAccept: */*;q=0.8, text/*, application/json;q=0.8, \
+                    Text/HTML\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+
+        assert_eq!(request.accept(),
+                   vec![("text/html".to_string(), 1.0),
+                        ("text/*".to_string(), 1.0),
+                        ("application/json".to_string(), 0.8),
+                        ("*/*".to_string(), 0.8)]);
+
+        assert_eq!(request.preferred_media_type(&["text/plain", "text/html"]),
+                   Some("text/html"));
+        assert_eq!(request.preferred_media_type(&["image/png", "application/json"]),
+                   Some("application/json"));
+    }
+
+    #[test]
+    fn preferred_media_type() {
+        let preferred = |accept: &str, available: &[&'static str]| {
+            let text = format!("GET / HTTP/1.1\r\nAccept: {}\r\n\r\n", accept);
+            Request::from_str(&text).unwrap().preferred_media_type(available)
+        };
+
+        // The most specific range decides, even with a lower quality
+        assert_eq!(preferred("text/*, text/plain;q=0.2", &["text/plain", "text/csv"]),
+                   Some("text/csv"));
+        assert_eq!(preferred("image/*, text/html;q=0", &["text/html"]), None);
+        assert_eq!(preferred("*/*", &["application/json", "text/html"]),
+                   Some("application/json"));
+
+        let request = Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.preferred_media_type(&["text/html"]), Some("text/html"));
+    }
+
+    #[test]
+    fn negotiate_compression() {
+        assert_eq!(negotiate("gzip;q=0.5, br", &["gzip", "br"]), Some("br"));
+        // Ties go to the server's preference
+        assert_eq!(negotiate("gzip, br", &["br", "gzip"]), Some("br"));
+        assert_eq!(negotiate("deflate", &["gzip", "br"]), None);
+        assert_eq!(negotiate("*;q=0.1, gzip;q=0", &["gzip", "br"]), Some("br"));
+
+        // Forbids identity and only accepts gzip
+        assert_eq!(negotiate("gzip, identity;q=0", &["br", "gzip"]), Some("gzip"));
+        assert_eq!(negotiate("gzip, *;q=0", &["br", "gzip", "identity"]), Some("gzip"));
+        assert_eq!(negotiate("gzip;q=0.1, identity", &["gzip", "identity"]), None);
+
+        let request = Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.negotiate_compression(&["gzip"]), None);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::net::{SocketAddr, TcpStream};
+
+use config::ParseConfig;
+use incremental::ParseProgress;
+use request::{is_safe_header_value, normalize_header_name, HttpVersion, Method, ParserError,
+              Request};
+
+/// A request that owns its data, for when it has to outlive the buffer it was parsed from,
+/// like one read from a connection with `from_reader`
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct OwnedRequest {
+    pub method: Method,
+    pub url: String,
+    pub version: HttpVersion,
+    pub headers: HashMap<String, String>,
+    pub(crate) header_text: String,
+    pub body: Vec<u8>,
+
+    // Where the request came from, which the bytes themselves can't tell
+    remote_addr: Option<SocketAddr>,
+}
+
+impl OwnedRequest {
+    /// Reads one request from the reader, body included, and leaves anything after it in
+    /// the reader's buffer for the next one. A request without Content-Length or chunked
+    /// encoding has no body, so nothing past its headers is read.
+    ///
+    /// Ends with `ParserError::Incomplete` if the reader runs out before the request does.
+    pub fn from_reader<R: BufRead>(reader: &mut R) -> Result<OwnedRequest, ParserError> {
+        OwnedRequest::from_reader_with_config(reader, &ParseConfig::default())
+    }
+
+    pub fn from_reader_with_config<R: BufRead>(reader: &mut R,
+                                               config: &ParseConfig)
+                                               -> Result<OwnedRequest, ParserError> {
+        let mut buffer = Vec::new();
+        let mut progress = ParseProgress::default();
+
+        loop {
+            let (parsed, available) = {
+                let available = try!(reader.fill_buf());
+                if available.is_empty() {
+                    return Err(ParserError::Incomplete);
+                }
+                buffer.extend_from_slice(available);
+
+                let parsed = match progress.parse(&buffer, config) {
+                    Ok((request, consumed)) => Some((request.into_owned(), consumed)),
+                    Err(ParserError::Incomplete) => None,
+                    Err(err) => return Err(err),
+                };
+
+                (parsed, available.len())
+            };
+
+            match parsed {
+                Some((request, consumed)) => {
+                    // Everything before this read was too little, so the request ends
+                    // somewhere in the bytes just read
+                    reader.consume(available - (buffer.len() - consumed));
+                    return Ok(request);
+                }
+                None => reader.consume(available),
+            }
+        }
+    }
+
+    /// Reads one request like `from_reader` from a connection, remembering the peer's
+    /// address as the request's `remote_addr`
+    pub fn from_stream(stream: &mut BufReader<TcpStream>) -> Result<OwnedRequest, ParserError> {
+        let mut request = try!(OwnedRequest::from_reader(stream));
+        request.remote_addr = stream.get_ref().peer_addr().ok();
+        Ok(request)
+    }
+
+    /// The address of the client that sent the request, if it is known
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    pub fn set_remote_addr(&mut self, remote_addr: SocketAddr) {
+        self.remote_addr = Some(remote_addr);
+    }
+
+    /// Sets a header, replacing any value it had, like middleware adding an X-Request-ID
+    /// before forwarding the request. Fails with `ParserError::InvalidHeader` if the name
+    /// isn't a token or the value isn't safe to send (see `is_safe_header_value`).
+    ///
+    /// Only `headers` changes, `Request::header_values` still returns the headers as they
+    /// were received.
+    pub fn insert_header(&mut self, name: &str, value: &str) -> Result<(), ParserError> {
+        let name = try!(normalize_header_name(name));
+        if !is_safe_header_value(value) {
+            return Err(ParserError::InvalidHeader(name));
+        }
+
+        self.headers.insert(name, value.to_string());
+        Ok(())
+    }
+
+    /// Removes a header, matching the name case-insensitively, and returns its value
+    pub fn remove_header(&mut self, name: &str) -> Option<String> {
+        self.headers.remove(&name.to_ascii_lowercase())
+    }
+
+    /// Serializes the request like `Request::to_bytes`, with any headers changed since
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_request().to_bytes()
+    }
+
+    /// Borrows the request as a `Request`, to use the methods only that one has
+    pub fn as_request(&self) -> Request<'_> {
+        Request {
+            method: self.method.clone(),
+            url: &self.url,
+            version: self.version,
+            headers: self.headers.clone(),
+            header_text: &self.header_text,
+            body: &self.body,
+        }
+    }
+}
+
+impl<'a> Request<'a> {
+    pub fn into_owned(self) -> OwnedRequest {
+        OwnedRequest {
+            method: self.method,
+            url: self.url.to_string(),
+            version: self.version,
+            headers: self.headers,
+            header_text: self.header_text.to_string(),
+            body: self.body.to_vec(),
+            remote_addr: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn unframed_request_has_no_body() {
+        let mut reader = BufReader::new(&b"GET /first HTTP/1.1\r\nHost: a\r\n\r\nGET /second \
+                                            HTTP/1.1\r\n\r\n"[..]);
+
+        let first = OwnedRequest::from_reader(&mut reader).unwrap();
+        assert_eq!(first.url, "/first");
+        assert!(first.body.is_empty());
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "GET /second HTTP/1.1\r\n\r\n");
+
+        let request = Request::from_str("GET / HTTP/1.1\r\nHost: a\r\n\r\ntrailing").unwrap();
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn read_body_across_reads() {
+        // A tiny buffer makes every read return a few bytes only
+        let mut reader = BufReader::with_capacity(4,
+                                                  &b"POST /upload HTTP/1.1\r\nContent-Length: \
+                                                     5\r\n\r\nhelloGET"[..]);
+
+        let request = OwnedRequest::from_reader(&mut reader).unwrap();
+        assert_eq!(request.body, b"hello");
+        assert_eq!(request.as_request().content_length().unwrap(), Some(5));
+        assert!(request.as_request().is_body_complete());
+
+        match OwnedRequest::from_reader(&mut reader) {
+            Err(ParserError::Incomplete) => (),
+            other => panic!("expected Incomplete, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_chunked_body_across_reads() {
+        let mut reader = BufReader::with_capacity(3,
+                                                  &b"POST /upload HTTP/1.1\r\nTransfer-Encoding: \
+                                                     chunked\n\r\n2\r\nhe\r\n3\r\nllo\r\n\
+                                                     0\r\n\r\nGET"[..]);
+
+        let request = OwnedRequest::from_reader(&mut reader).unwrap();
+        assert_eq!(request.body, b"2\r\nhe\r\n3\r\nllo\r\n0\r\n\r\n");
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "GET");
+    }
+
+    #[test]
+    fn remote_addr() {
+        let mut request = Request::from_str("GET / HTTP/1.1\r\n\r\n").unwrap().into_owned();
+        assert_eq!(request.remote_addr(), None);
+
+        let addr = "192.0.2.1:4321".parse().unwrap();
+        request.set_remote_addr(addr);
+        assert_eq!(request.remote_addr(), Some(addr));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+
+        let mut stream = BufReader::new(listener.accept().unwrap().0);
+        let request = OwnedRequest::from_stream(&mut stream).unwrap();
+        assert_eq!(request.remote_addr(), Some(client.local_addr().unwrap()));
+    }
+
+    #[test]
+    fn modify_headers() {
+        let text = "GET /api HTTP/1.1\r\nHost: a\r\nCookie: secret\r\n\r\n";
+        let mut request = Request::from_str(text).unwrap().into_owned();
+
+        request.insert_header("traceparent",
+                              "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+               .unwrap();
+        request.insert_header("Host", "b").unwrap();
+        assert_eq!(request.remove_header("COOKIE"), Some("secret".to_string()));
+
+        assert_eq!(String::from_utf8(request.to_bytes()).unwrap(),
+                   "GET /api HTTP/1.1\r\nhost: b\r\ntraceparent: \
+                    00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01\r\n\r\n");
+
+        assert!(request.insert_header("X-Test", "a\r\nInjected: yes").is_err());
+        assert!(request.insert_header("Bad Name", "a").is_err());
+        assert_eq!(request.headers.len(), 2);
+    }
+}
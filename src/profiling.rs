@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use config::ParseConfig;
+use request::{parse_request_line, split_head, ParserError, Request};
+
+/// How long each phase of parsing a request took
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct ParseStats {
+    pub request_line: Duration,
+    // Finding where the headers end and parsing them
+    pub headers: Duration,
+    // Finding where the body ends, including the chunk sizes of a chunked body
+    pub body: Duration,
+}
+
+impl ParseStats {
+    pub fn total(&self) -> Duration {
+        self.request_line + self.headers + self.body
+    }
+}
+
+impl<'b> Request<'b> {
+    /// Parses like `from_str_with_config`, timing each phase on the way
+    pub fn from_str_profiled<'a>(request_text: &'a str,
+                                 config: &ParseConfig)
+                                 -> Result<(Request<'a>, ParseStats), ParserError> {
+        let start = Instant::now();
+        let line = try!(parse_request_line(request_text, config));
+        let request_line = start.elapsed();
+
+        let start = Instant::now();
+        let head = split_head(line);
+        let rest = head.rest;
+        let mut request = try!(Request::from_head(head, config));
+        let headers = start.elapsed();
+
+        let start = Instant::now();
+        try!(request.take_body_leniently(rest));
+        let body = start.elapsed();
+
+        Ok((request,
+            ParseStats {
+                request_line: request_line,
+                headers: headers,
+                body: body,
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_populated() {
+        let mut text = "POST /upload HTTP/1.1\r\n".to_string();
+        for i in 0..1000 {
+            text.push_str(&format!("X-Header-{}: {}\r\n", i, i));
+        }
+        text.push_str("Content-Length: 4\r\n\r\nbody");
+
+        let (request, stats) = Request::from_str_profiled(&text, &ParseConfig::default()).unwrap();
+
+        assert_eq!(request.body, b"body");
+        assert!(stats.request_line > Duration::new(0, 0));
+        assert!(stats.headers > Duration::new(0, 0));
+        assert!(stats.total() >= stats.headers);
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::{self, Write};
+
+use headers::{parse_param, split_list_header, split_unquoted};
+use request::{is_absolute_form, HttpVersion, Method, Request};
+
+// Headers that only apply to a single connection and must not be forwarded (RFC 7230
+// section 6.1), besides any that the Connection header itself names
+pub const HOP_BY_HOP_HEADERS: [&'static str; 8] = ["connection",
+                                                   "keep-alive",
+                                                   "proxy-authenticate",
+                                                   "proxy-authorization",
+                                                   "te",
+                                                   "trailer",
+                                                   "transfer-encoding",
+                                                   "upgrade"];
+
+impl<'a> Request<'a> {
+    /// Removes the hop-by-hop headers, including those named by the Connection header
+    pub fn remove_hop_by_hop_headers(&mut self) {
+        let named = self.header_list("connection")
+                        .iter()
+                        .map(|name| name.to_lowercase())
+                        .collect::<Vec<_>>();
+
+        for name in &named {
+            self.headers.remove(name);
+        }
+        for name in HOP_BY_HOP_HEADERS.iter() {
+            self.headers.remove(*name);
+        }
+    }
+
+    /// Adjusts a request to be forwarded to an HTTP/1.0 server: the version becomes 1.0,
+    /// hop-by-hop headers (chunked Transfer-Encoding included) are removed and the
+    /// connection is marked to close, as persistent connections are opt-in in 1.0.
+    ///
+    /// HTTP/1.0 has no chunked encoding, so for a chunked request the body has to be
+    /// buffered and decoded and sent with a Content-Length instead. `body` is left as it
+    /// was sent, it's up to the caller to replace it and set the Content-Length.
+    pub fn downgrade_to_1_0(&mut self) {
+        self.version = HttpVersion::new(1, 0);
+        self.remove_hop_by_hop_headers();
+
+        // 1.0 servers don't know 100-continue either
+        self.headers.remove("expect");
+        self.headers.insert("connection".to_string(), "close".to_string());
+    }
+
+    /// Writes the request to the upstream server as a reverse proxy forwards it: without
+    /// the hop-by-hop headers, but with its Transfer-Encoding, since the body is forwarded
+    /// as it was received. Connection is dropped with the other hop-by-hop headers rather
+    /// than rewritten, so the upstream connection is persistent or not by its own default.
+    ///
+    /// A chunked request loses its Content-Length (RFC 7230 section 3.3.3). The body was
+    /// read as chunked, and an upstream going by the length would see a different request.
+    pub fn forward_to<W: Write>(&self, upstream: &mut W) -> io::Result<()> {
+        let mut forwarded = self.clone();
+        forwarded.remove_hop_by_hop_headers();
+
+        if let Some(encodings) = self.headers.get("transfer-encoding") {
+            forwarded.headers.insert("transfer-encoding".to_string(), encodings.clone());
+        }
+        if self.is_chunked() {
+            forwarded.headers.remove("content-length");
+        }
+
+        try!(upstream.write_all(&forwarded.to_bytes()));
+        upstream.flush()
+    }
+
+    /// Whether an absolute-form target (`GET gopher://host/ HTTP/1.1`) uses one of the
+    /// schemes a proxy is willing to forward, compared case-insensitively. Any other target
+    /// has no scheme of its own and is fine.
+    pub fn has_supported_scheme(&self, schemes: &[&str]) -> bool {
+        if !is_absolute_form(self.url) {
+            return true;
+        }
+
+        let scheme = self.url.splitn(2, "://").next().unwrap_or("");
+        schemes.iter().any(|supported| supported.eq_ignore_ascii_case(scheme))
+    }
+
+    /// The host and port a CONNECT request asks to open a tunnel to, from its authority-form
+    /// target like `example.com:443`. An IPv6 address comes without its brackets. `None`
+    /// for other methods and for targets that aren't a host with a port.
+    pub fn connect_target(&self) -> Option<(&str, u16)> {
+        if self.method != Method::CONNECT {
+            return None;
+        }
+
+        let colon = try_opt!(self.url.rfind(':'));
+        let (host, port) = (&self.url[..colon], &self.url[colon + 1..]);
+
+        let host = if host.starts_with('[') && host.ends_with(']') {
+            &host[1..host.len() - 1]
+        } else if host.contains(':') {
+            return None;
+        } else {
+            host
+        };
+
+        if host.is_empty() || host.contains(|c| c == '/' || c == '@' || c == '[' || c == ']') ||
+           !port.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        match port.parse() {
+            Ok(0) | Err(_) => None,
+            Ok(port) => Some((host, port)),
+        }
+    }
+
+    /// The scheme the client used with the proxy in front of us, `"http"` or `"https"`.
+    /// Taken from the `proto` of the first (the client's) element of the Forwarded header
+    /// (RFC 7239) if there is one, otherwise from X-Forwarded-Proto. Only meaningful if
+    /// the proxy is trusted to overwrite these headers.
+    pub fn forwarded_proto(&self) -> Option<&'static str> {
+        let proto = match self.headers.get("forwarded") {
+            Some(value) => {
+                let element = try_opt!(split_list_header(value).into_iter().next());
+                try_opt!(split_unquoted(element, ';', false)
+                             .into_iter()
+                             .map(parse_param)
+                             .find(|&(ref name, _)| name == "proto")
+                             .map(|(_, value)| value))
+            }
+            None => try_opt!(self.header_list("x-forwarded-proto").into_iter().next()).to_string(),
+        };
+
+        if proto.eq_ignore_ascii_case("https") {
+            Some("https")
+        } else if proto.eq_ignore_ascii_case("http") {
+            Some("http")
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downgrade_chunked_request() {
+        let mut request = Request::from_str("POST /upload HTTP/1.1\r\nHost: example.com\r\n\
+                                             Transfer-Encoding: chunked\r\nConnection: \
+                                             keep-alive, X-Trace\r\nX-Trace: 1\r\nX-Other: \
+                                             2\r\n\r\n4\r\ndata\r\n0\r\n\r\n")
+                              .unwrap();
+        request.downgrade_to_1_0();
+
+        assert_eq!(request.version, HttpVersion::new(1, 0));
+        assert_eq!(request.headers.get("transfer-encoding"), None);
+        assert_eq!(request.headers.get("x-trace"), None);
+        assert_eq!(request.headers.get("connection"), Some(&"close".to_string()));
+        assert_eq!(request.headers.get("host"), Some(&"example.com".to_string()));
+        assert_eq!(request.headers.get("x-other"), Some(&"2".to_string()));
+        // Still chunked, the caller has to decode it
+        assert_eq!(request.body, b"4\r\ndata\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn forwarded_proto() {
+        let request = |headers: &str| {
+            Request::from_str(&format!("GET / HTTP/1.1\r\n{}\r\n", headers))
+                .unwrap()
+                .forwarded_proto()
+        };
+
+        assert_eq!(request("Forwarded: for=192.0.2.60;proto=HTTPS;by=203.0.113.43, \
+                            for=10.0.0.1;proto=http\r\n"),
+                   Some("https"));
+        assert_eq!(request("X-Forwarded-Proto: https, http\r\n"), Some("https"));
+        assert_eq!(request("Forwarded: proto=\"http\"\r\nX-Forwarded-Proto: https\r\n"),
+                   Some("http"));
+        assert_eq!(request("X-Forwarded-Proto: gopher\r\n"), None);
+        assert_eq!(request(""), None);
+    }
+
+    #[test]
+    fn supported_scheme() {
+        let supported = |url: &str| {
+            let text = format!("GET {} HTTP/1.1\r\n\r\n", url);
+            Request::from_str(&text).unwrap().has_supported_scheme(&["http", "https"])
+        };
+
+        assert!(supported("HTTP://example.com/"));
+        assert!(supported("/index.html"));
+        assert!(!supported("file:///etc/passwd"));
+        assert!(!supported("gopher://example.com/"));
+    }
+
+    #[test]
+    fn connect_target() {
+        let target = |line: &str| {
+            let text = format!("{}\r\n\r\n", line);
+            Request::from_str(&text)
+                .unwrap()
+                .connect_target()
+                .map(|(host, port)| (host.to_string(), port))
+        };
+
+        assert_eq!(target("CONNECT example.com:443 HTTP/1.1"),
+                   Some(("example.com".to_string(), 443)));
+        assert_eq!(target("CONNECT [2001:db8::1]:8443 HTTP/1.1"),
+                   Some(("2001:db8::1".to_string(), 8443)));
+        assert_eq!(target("CONNECT example.com HTTP/1.1"), None);
+        assert_eq!(target("CONNECT example.com: HTTP/1.1"), None);
+        assert_eq!(target("CONNECT 2001:db8::1 HTTP/1.1"), None);
+        assert_eq!(target("GET example.com:443 HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn forward_request() {
+        let request = Request::from_str("POST /api HTTP/1.1\r\nHost: backend\r\nConnection: \
+                                         keep-alive, X-Hop\r\nX-Hop: 1\r\nKeep-Alive: \
+                                         timeout=5\r\nTransfer-Encoding: chunked\r\n\r\n\
+                                         2\r\nhi\r\n0\r\n\r\n")
+                          .unwrap();
+
+        let mut upstream = Vec::new();
+        request.forward_to(&mut upstream).unwrap();
+
+        assert_eq!(String::from_utf8(upstream).unwrap(),
+                   "POST /api HTTP/1.1\r\nhost: backend\r\ntransfer-encoding: chunked\r\n\r\n\
+                    2\r\nhi\r\n0\r\n\r\n");
+    }
+    #[test]
+    fn forward_chunked_without_content_length() {
+        let request = Request::from_str("POST /api HTTP/1.1\r\nContent-Length: 4\r\n\
+                                         Transfer-Encoding: chunked\r\n\r\n\
+                                         2\r\nhi\r\n0\r\n\r\n")
+                          .unwrap();
+
+        let mut upstream = Vec::new();
+        request.forward_to(&mut upstream).unwrap();
+
+        assert_eq!(String::from_utf8(upstream).unwrap(),
+                   "POST /api HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+                    2\r\nhi\r\n0\r\n\r\n");
+    }
+}
@@ -1,13 +1,43 @@
+use std::borrow::Cow;
+use std::cmp;
 use std::collections::HashMap;
-use std::{str, u8};
+use std::convert::TryFrom;
+use std::io::Read;
+use std::iter::Peekable;
+use std::mem;
+use std::{fmt, io, str, u8};
+use std::str::Lines;
 
-#[derive(Eq, PartialEq, Debug)]
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use chunked;
+use headers::split_list_header;
+use config::ParseConfig;
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct HttpVersion {
     major: u8,
     minor: u8,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+impl HttpVersion {
+    pub fn new(major: u8, minor: u8) -> HttpVersion {
+        HttpVersion {
+            major: major,
+            minor: minor,
+        }
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HTTP/{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub struct Request<'a> {
     pub method: Method,
     pub url: &'a str,
@@ -15,27 +45,207 @@ pub struct Request<'a> {
 
     // Header names are lowercased so we need a String to modify them
     pub headers: HashMap<String, String>,
+
+    // The header block as received, for the headers that occur more than once
+    pub(crate) header_text: &'a str,
+
+    // The message body as framed by Content-Length or chunked encoding. Chunked bodies
+    // are kept in their encoded form.
+    pub body: &'a [u8],
 }
 
 #[derive(Debug, Clone)]
 pub enum ParserError {
+    BodyTooLarge,
+    ConflictingBodyFraming,
+    HeadersTooLarge,
+    Incomplete,
+    InvalidChunkedBody,
+    InvalidEncoding(String),
+    InvalidContentLength(String),
     InvalidFormat,
+    InvalidFrame(String),
     InvalidHeader(String),
     InvalidHttpVersion,
     InvalidInitialLine(String),
+    Io(io::ErrorKind),
+    // A method the config doesn't allow, see `ParseConfig::allow_trace`
+    MethodNotAllowed(String),
+    RequestLineTooLong,
+    Serialization(String),
+    // More query parameters than `ParseConfig::max_query_params` allows
+    TooManyParams,
+    // A multipart body that ends before its closing boundary
+    TruncatedMultipart,
+    UnsupportedEncoding(String),
+    // A version the config doesn't accept, see `ParseConfig::accepted_versions`
+    UnsupportedVersion(HttpVersion),
     Uft8Error(str::Utf8Error),
+    // An error along with the start of the request that caused it, see `with_context`
+    WithContext(Box<ParserError>, String),
 }
 
-#[derive(Eq, PartialEq, Debug)]
+impl ParserError {
+    /// Wraps the error along with up to `max_length` bytes from the start of the input,
+    /// so whoever looks into the error later can see what the request looked like
+    pub fn with_context(self, input: &str, max_length: usize) -> ParserError {
+        let mut length = cmp::min(max_length, input.len());
+        while !input.is_char_boundary(length) {
+            length -= 1;
+        }
+
+        ParserError::WithContext(Box::new(self), input[..length].to_string())
+    }
+
+    /// The start of the request captured by `with_context`, if any
+    pub fn context(&self) -> Option<&str> {
+        match *self {
+            ParserError::WithContext(_, ref context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The error itself, without any context wrapped around it
+    pub fn without_context(&self) -> &ParserError {
+        match *self {
+            ParserError::WithContext(ref err, _) => err.without_context(),
+            ref err => err,
+        }
+    }
+
+    fn is_incomplete(&self) -> bool {
+        match *self.without_context() {
+            ParserError::Incomplete => true,
+            _ => false,
+        }
+    }
+}
+
+/// Something the parser accepted only because the config told it to be lenient
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum ParseWarning {
+    // The method as it was sent, before its case was normalized
+    MethodCaseNormalized(String),
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum Method {
+    CONNECT,
     DELETE,
     GET,
+    HEAD,
     POST,
     PUT,
+    TRACE,
     UPDATE,
     UNSUPPORTED(String),
 }
 
+impl Method {
+    pub fn as_str(&self) -> &str {
+        match <&'static str>::try_from(self) {
+            Ok(name) => name,
+            Err(name) => name,
+        }
+    }
+
+    /// Whether repeating the request has the same effect as sending it once (RFC 7231
+    /// section 4.2.2). Unsupported methods are assumed not to be.
+    pub fn is_idempotent(&self) -> bool {
+        use self::Method::*;
+
+        match *self {
+            DELETE | GET | HEAD | PUT | TRACE => true,
+            CONNECT | POST | UPDATE | UNSUPPORTED(_) => false,
+        }
+    }
+}
+
+/// Only the methods we know have a `'static` name, for an unsupported method the error is
+/// its name as it was sent
+impl<'a> TryFrom<&'a Method> for &'static str {
+    type Error = &'a str;
+
+    fn try_from(method: &'a Method) -> Result<&'static str, &'a str> {
+        use self::Method::*;
+
+        match *method {
+            CONNECT => Ok("CONNECT"),
+            DELETE => Ok("DELETE"),
+            GET => Ok("GET"),
+            HEAD => Ok("HEAD"),
+            POST => Ok("POST"),
+            PUT => Ok("PUT"),
+            TRACE => Ok("TRACE"),
+            UPDATE => Ok("UPDATE"),
+            UNSUPPORTED(ref method) => Err(method),
+        }
+    }
+}
+
+/// A method that borrows the name of an unsupported one instead of copying it, for
+/// classifying methods without allocating, like when most of them are garbage anyway
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum MethodRef<'a> {
+    CONNECT,
+    DELETE,
+    GET,
+    HEAD,
+    POST,
+    PUT,
+    TRACE,
+    UPDATE,
+    UNSUPPORTED(&'a str),
+}
+
+pub fn classify_method(method: &str) -> MethodRef<'_> {
+    use self::MethodRef::*;
+
+    match method {
+        "CONNECT" => CONNECT,
+        "DELETE" => DELETE,
+        "GET" => GET,
+        "HEAD" => HEAD,
+        "POST" => POST,
+        "PUT" => PUT,
+        "TRACE" => TRACE,
+        "UPDATE" => UPDATE,
+        _ => UNSUPPORTED(method),
+    }
+}
+
+// Like `classify_method`, but UPDATE is only a method if the config recognizes it
+pub(crate) fn classify_method_with_config<'a>(method: &'a str,
+                                              config: &ParseConfig)
+                                              -> MethodRef<'a> {
+    match classify_method(method) {
+        MethodRef::UPDATE if !config.recognize_update_method => MethodRef::UNSUPPORTED(method),
+        classified => classified,
+    }
+}
+
+impl<'a> From<MethodRef<'a>> for Method {
+    fn from(method: MethodRef<'a>) -> Method {
+        match method {
+            MethodRef::CONNECT => Method::CONNECT,
+            MethodRef::DELETE => Method::DELETE,
+            MethodRef::GET => Method::GET,
+            MethodRef::HEAD => Method::HEAD,
+            MethodRef::POST => Method::POST,
+            MethodRef::PUT => Method::PUT,
+            MethodRef::TRACE => Method::TRACE,
+            MethodRef::UPDATE => Method::UPDATE,
+            MethodRef::UNSUPPORTED(method) => Method::UNSUPPORTED(method.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for ParserError {
+    fn from(err: io::Error) -> Self {
+        ParserError::Io(err.kind())
+    }
+}
+
 impl From<str::Utf8Error> for ParserError {
     fn from(err: str::Utf8Error) -> Self {
         ParserError::Uft8Error(err)
@@ -43,90 +253,335 @@ impl From<str::Utf8Error> for ParserError {
 }
 
 impl<'b> Request<'b> {
+    /// Parses a request, lenient about missing parts at the end of the text: if the
+    /// headers aren't terminated the request has no body, and a body shorter than its
+    /// framing promises is returned as far as it goes.
     pub fn from_str<'a>(request_text: &'a str) -> Result<Request<'a>, ParserError> {
-        use self::ParserError::*;
-
-        // Parse the initial line
-        let mut split_at_initial_line = request_text.splitn(2, '\n');
-        let initial_line = try!(split_at_initial_line.next()
-                                                     .ok_or(InvalidInitialLine(String::new())));
-
-        let (method, url, version) = try!(match initial_line.split_whitespace()
-                                                            .collect::<Vec<_>>()
-                                                            .as_slice() {
-            [method, url, version] => {
-                use self::Method::*;
-
-                let method = match method {
-                    "DELETE" => DELETE,
-                    "GET" => GET,
-                    "POST" => POST,
-                    "PUT" => PUT,
-                    "UPDATE" => UPDATE,
-                    _ => UNSUPPORTED(method.to_string()),
-                };
+        Request::from_str_with_config(request_text, &ParseConfig::default())
+    }
 
-                Ok((method, url, try!(Request::parse_version(version))))
-            }
-            _ => Err(InvalidInitialLine(initial_line.to_string())),
-        });
+    pub fn from_str_with_config<'a>(request_text: &'a str,
+                                    config: &ParseConfig)
+                                    -> Result<Request<'a>, ParserError> {
+        Request::from_str_with_warnings(request_text, config).map(|(request, _)| request)
+    }
+
+    /// Like `from_str_with_config`, but also returns what the parser had to let slide
+    /// because of lenient options in the config
+    ///
+    /// With `ParseConfig::capture_context` set, an error other than `Incomplete` comes
+    /// wrapped in `ParserError::WithContext` along with the start of the request.
+    pub fn from_str_with_warnings<'a>(request_text: &'a str,
+                                      config: &ParseConfig)
+                                      -> Result<(Request<'a>, Vec<ParseWarning>), ParserError> {
+        Request::parse_str(request_text, config).map_err(|err| match config.capture_context {
+            Some(max_length) if !err.is_incomplete() => err.with_context(request_text, max_length),
+            _ => err,
+        })
+    }
+
+    fn parse_str<'a>(request_text: &'a str,
+                     config: &ParseConfig)
+                     -> Result<(Request<'a>, Vec<ParseWarning>), ParserError> {
+        let mut head = try!(parse_head(request_text, config));
+        let rest = head.rest;
+        let warnings = mem::replace(&mut head.warnings, Vec::new());
 
-        let remaining_request = try!(split_at_initial_line.next().ok_or(InvalidFormat));
+        let mut request = try!(Request::from_head(head, config));
+        try!(request.take_body_leniently(rest));
 
-        let empty_line = if initial_line.ends_with('\r') {
-            "\r\n\r\n"
+        Ok((request, warnings))
+    }
+
+    /// Parses a request from the start of the buffer and returns it along with the number
+    /// of bytes it took up, so a buffer holding several pipelined requests can be parsed
+    /// one after another. Unlike `from_str` this fails with `ParserError::Incomplete`
+    /// unless the whole request, body included, is already in the buffer.
+    pub fn from_bytes<'a>(bytes: &'a [u8]) -> Result<(Request<'a>, usize), ParserError> {
+        Request::from_bytes_with_config(bytes, &ParseConfig::default())
+    }
+
+    pub fn from_bytes_with_config<'a>(bytes: &'a [u8],
+                                      config: &ParseConfig)
+                                      -> Result<(Request<'a>, usize), ParserError> {
+        // Don't wait for the rest of the head if the initial line is already too long
+        try!(check_request_line_length(bytes, config));
+
+        let too_large = |length: usize| config.max_total_bytes.map_or(false, |max| length > max);
+        // Waiting for more is pointless once we have more than the whole request may take
+        let incomplete = || if too_large(bytes.len()) {
+            ParserError::BodyTooLarge
         } else {
-            "\n\n"
+            ParserError::Incomplete
         };
 
-        let mut split_at_empty_line = remaining_request.splitn(2, empty_line);
+        let head_length = try!(head_length(bytes, 0).ok_or_else(&incomplete));
+        let head = try!(parse_head(try!(str::from_utf8(&bytes[..head_length])), config));
 
-        let header_text = try!(split_at_empty_line.next().ok_or(InvalidFormat));
+        let mut request = try!(Request::from_head(head, config));
 
-        Ok(Request {
-            method: method,
-            url: url,
-            version: version,
-            headers: try!(Request::parse_headers(header_text)),
-        })
+        // Don't wait for a body that is announced to be too large
+        if let Some(length) = try!(request.content_length()) {
+            if !request.is_chunked() && too_large(head_length.saturating_add(length)) {
+                return Err(ParserError::BodyTooLarge);
+            }
+        }
+
+        let rest = &bytes[head_length..];
+        let body_length = try!(try!(request.body_length(rest)).ok_or_else(&incomplete));
+        if too_large(head_length + body_length) {
+            return Err(ParserError::BodyTooLarge);
+        }
+        request.body = &rest[..body_length];
+
+        Ok((request, head_length + body_length))
     }
 
-    fn parse_headers<'a>(header_text: &'a str) -> Result<HashMap<String, String>, ParserError> {
-        use self::ParserError::InvalidHeader;
+    pub(crate) fn from_head<'a>(head: Head<'a>,
+                                config: &ParseConfig)
+                                -> Result<Request<'a>, ParserError> {
+        let request = Request {
+            method: head.method,
+            url: head.url,
+            version: head.version,
+            headers: try!(Request::parse_headers(head.header_text, config)),
+            header_text: head.header_text,
+            body: &[],
+        };
+
+        // The body is framed with plain `content_length`, so check the stricter form here
+        if config.canonical_content_length {
+            try!(request.content_length_with_config(config));
+        }
+        Ok(request)
+    }
 
-        let mut header_lines = header_text.lines().peekable();
+    /// The value of the Content-Length header, if there is one. Anything but a plain
+    /// decimal number is an error: no sign, no surrounding whitespace, no list of values
+    /// and no repeated lines that disagree, since lenient parsing here is what request
+    /// smuggling feeds on. Leading zeros are part of a decimal number though, so `007` is
+    /// 7, unless the config's `canonical_content_length` says otherwise.
+    pub fn content_length(&self) -> Result<Option<usize>, ParserError> {
+        self.content_length_with_config(&ParseConfig::default())
+    }
 
-        let mut headers = HashMap::<String, String>::new();
+    pub fn content_length_with_config(&self,
+                                      config: &ParseConfig)
+                                      -> Result<Option<usize>, ParserError> {
+        match self.headers.get("content-length") {
+            Some(value) => {
+                let err = || ParserError::InvalidContentLength(value.to_string());
 
-        while let Some(line) = header_lines.next() {
-            // If this line in a continuation of another header value ignore it
-            if line.trim_left().len() == line.len() {
-                let err = InvalidHeader(line.to_string());
+                // The map only keeps the last of repeated lines, which is fine as long as
+                // they all agree (RFC 7230 section 3.3.2)
+                let values = self.header_values("content-length");
+                if values.iter().any(|other| other != value) {
+                    return Err(ParserError::InvalidContentLength(values.join(", ")));
+                }
+                if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return Err(err());
+                }
+                if config.canonical_content_length && value.len() > 1 && value.starts_with('0') {
+                    return Err(err());
+                }
 
-                let mut parts = line.splitn(2, ':');
-                let name = try!(parts.next().ok_or(err.clone())).trim_right().to_lowercase();
-                let value = try!(parts.next().ok_or(err.clone())).trim_left();
+                value.parse::<usize>().map(Some).map_err(|_| err())
+            }
+            None => Ok(None),
+        }
+    }
 
-                let value_continuation = if let Some(next_header) = header_lines.peek() {
-                    // If the next header begins with whitespace it should be
-                    // interpreted as a continuation of the previous header's value
-                    if next_header.trim_left().len() != next_header.len() {
-                        Some(next_header.trim_left())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
+    /// Serializes the request into its HTTP/1 wire form, with the headers sorted by name
+    /// and the body as it was received
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!("{} {} {}\r\n", self.method.as_str(), self.url, self.version);
 
-                if let Some(value_continuation) = value_continuation {
-                    headers.insert(name, value.to_string() + " " + value_continuation);
-                } else {
-                    headers.insert(name, value.to_string());
+        for (name, value) in self.headers_sorted() {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(self.body);
+        bytes
+    }
+
+    /// The codings of the Transfer-Encoding header in the order they were applied
+    pub fn transfer_encodings(&self) -> Vec<&str> {
+        self.headers.get("transfer-encoding").map_or(Vec::new(), |value| split_list_header(value))
+    }
+
+    /// Whether the body uses chunked transfer encoding, which has to be the last coding
+    pub fn is_chunked(&self) -> bool {
+        self.transfer_encodings()
+            .last()
+            .map_or(false, |coding| coding.eq_ignore_ascii_case("chunked"))
+    }
+
+    /// Fails with `ParserError::ConflictingBodyFraming` if there is a Transfer-Encoding
+    /// that doesn't end with chunked. Only chunked says where a request body ends (RFC 7230
+    /// section 3.3.3), and guessing is how servers behind each other end up disagreeing.
+    pub fn validate_transfer_encoding(&self) -> Result<(), ParserError> {
+        let encodings = self.transfer_encodings();
+
+        if encodings.is_empty() || self.is_chunked() {
+            Ok(())
+        } else {
+            Err(ParserError::ConflictingBodyFraming)
+        }
+    }
+
+    /// Whether the next request on the connection can be read right after this one's
+    /// headers, which is only the case if this request has no body to consume first.
+    /// Any framed body counts, whatever the method, and so does a Content-Length that
+    /// can't be trusted to say where the body ends.
+    pub fn is_pipeline_safe(&self) -> bool {
+        self.transfer_encodings().is_empty() &&
+        match self.content_length() {
+            Ok(length) => length.unwrap_or(0) == 0,
+            Err(_) => false,
+        }
+    }
+
+    /// Whether `body` holds the whole body its framing announces. Always the case after
+    /// `from_bytes` or `OwnedRequest::from_reader`, but `from_str` takes a body that falls
+    /// short of its Content-Length or final chunk as far as it goes.
+    pub fn is_body_complete(&self) -> bool {
+        match self.body_length(self.body) {
+            Ok(Some(length)) => length == self.body.len(),
+            _ => false,
+        }
+    }
+
+    /// A hash of the body as it was received, `None` if there is no body. The hash is
+    /// 64 bit FNV-1a, which is the same in every run and on every platform, unlike the
+    /// randomly seeded hasher of `HashMap`, so it can key a cache shared between processes.
+    /// It's not cryptographic, a client can make two bodies collide on purpose.
+    pub fn body_hash(&self) -> Option<u64> {
+        if self.body.is_empty() {
+            return None;
+        }
+
+        Some(self.body.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        }))
+    }
+
+    /// Reads the body from the connection the head came from, for when only the head was
+    /// read and parsed, e.g. with `from_str` up to the empty line. A chunked body is
+    /// decoded and one with a Content-Length read up to that length, and the reader is left
+    /// right after either. A body larger than `ParseConfig::max_body_size` is
+    /// `ParserError::BodyTooLarge` without being read, and one the reader ends in the
+    /// middle of is `Incomplete`.
+    pub fn read_body<R: Read>(&self,
+                              conn: &mut R,
+                              config: &ParseConfig)
+                              -> Result<Vec<u8>, ParserError> {
+        try!(self.validate_transfer_encoding());
+        let mut body = Vec::new();
+
+        if self.is_chunked() {
+            for chunk in chunked::ChunkedDecoder::unbuffered(conn, config.max_body_size) {
+                body.extend_from_slice(&try!(chunk));
+            }
+        } else if let Some(length) = try!(self.content_length()) {
+            if config.max_body_size.map_or(false, |max| length > max) {
+                return Err(ParserError::BodyTooLarge);
+            }
+
+            try!(conn.take(length as u64).read_to_end(&mut body));
+            if body.len() < length {
+                return Err(ParserError::Incomplete);
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Whether a GET, HEAD or DELETE request has a body. Those methods have no use for one,
+    /// so it's suspicious, possibly an attempt at request smuggling.
+    pub fn has_unexpected_body(&self) -> bool {
+        match self.method {
+            Method::GET | Method::HEAD | Method::DELETE => !self.is_pipeline_safe(),
+            _ => false,
+        }
+    }
+
+    /// The method the request is to be handled as. That's the one in X-HTTP-Method-Override
+    /// of a POST if the config allows overrides and names a supported method, otherwise
+    /// the method the request was sent with. Only POST can be overridden, since a GET
+    /// turning into a DELETE would make any link a way to delete something.
+    pub fn effective_method(&self, config: &ParseConfig) -> Method {
+        if config.allow_method_override && self.method == Method::POST {
+            if let Some(method) = self.headers.get("x-http-method-override") {
+                match Method::from(classify_method_with_config(method.trim(), config)) {
+                    Method::UNSUPPORTED(_) => (),
+                    method => return method,
                 }
             }
         }
 
+        self.method.clone()
+    }
+
+    // The body as `from_str` frames it from what follows the head, as far as it goes
+    pub(crate) fn take_body_leniently(&mut self, rest: Option<&'b str>) -> Result<(), ParserError> {
+        if let Some(rest) = rest.map(str::as_bytes) {
+            self.body = match try!(self.body_length(rest)) {
+                Some(length) => &rest[..length],
+                None => rest,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// How many bytes at the start of `rest` belong to this request's body, or `None` if
+    /// they don't hold the whole body yet. Requests without Content-Length or chunked
+    /// encoding have no body at all.
+    fn body_length(&self, rest: &[u8]) -> Result<Option<usize>, ParserError> {
+        try!(self.validate_transfer_encoding());
+
+        if self.is_chunked() {
+            chunked::chunked_length(rest)
+        } else if let Some(length) = try!(self.content_length()) {
+            Ok(if rest.len() >= length {
+                Some(length)
+            } else {
+                None
+            })
+        } else {
+            Ok(Some(0))
+        }
+    }
+
+    fn parse_headers<'a>(header_text: &'a str,
+                         config: &ParseConfig)
+                         -> Result<HashMap<String, String>, ParserError> {
+        let mut headers = HashMap::<String, String>::new();
+        let mut total_bytes = 0usize;
+
+        for field in header_fields(header_text) {
+            let (name, value) = try!(field);
+
+            total_bytes = total_bytes.saturating_add(name.len() + value.len());
+            if config.max_total_header_bytes.map_or(false, |max| total_bytes > max) {
+                return Err(ParserError::HeadersTooLarge);
+            }
+
+            let name = if config.validate_header_values {
+                if !is_safe_header_value(&value) ||
+                   value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+                    return Err(ParserError::InvalidHeader(name.to_string()));
+                }
+                try!(normalize_header_name(name))
+            } else {
+                name.to_lowercase()
+            };
+
+            headers.insert(name, value.into_owned());
+        }
+
         Ok(headers)
     }
 
@@ -151,9 +606,287 @@ impl<'b> Request<'b> {
     }
 }
 
+/// The parts of a request up to the empty line ending its headers
+pub(crate) struct Head<'a> {
+    pub method: Method,
+    pub url: &'a str,
+    pub version: HttpVersion,
+    pub header_text: &'a str,
+
+    // Everything after the empty line, or `None` if the headers weren't terminated
+    pub rest: Option<&'a str>,
+
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Parses the initial line and returns it alongside the (still unparsed) block of header lines
+pub(crate) fn parse_head<'a>(request_text: &'a str,
+                             config: &ParseConfig)
+                             -> Result<Head<'a>, ParserError> {
+    parse_request_line(request_text, config).map(split_head)
+}
+
+/// The initial line of a request, and the text it was parsed from
+pub(crate) struct RequestLine<'a> {
+    pub method: Method,
+    pub url: &'a str,
+    pub version: HttpVersion,
+    pub warnings: Vec<ParseWarning>,
+
+    // Without a byte order mark or whitespace before the method
+    request_text: &'a str,
+    // Everything after the initial line
+    remaining_request: &'a str,
+}
+
+/// The first half of `parse_head`, which stops at the end of the initial line
+pub(crate) fn parse_request_line<'a>(request_text: &'a str,
+                                     config: &ParseConfig)
+                                     -> Result<RequestLine<'a>, ParserError> {
+    use self::ParserError::*;
+
+    try!(check_request_line_length(request_text.as_bytes(), config));
+
+    let mut warnings = Vec::new();
+
+    // Some buggy clients start the request with a UTF-8 byte order mark
+    let request_text = if request_text.starts_with('\u{feff}') {
+        &request_text['\u{feff}'.len_utf8()..]
+    } else {
+        request_text
+    };
+    // Stray spaces or tabs before the method aren't part of it, unless we're strict about
+    // the initial line. Leading line endings are left alone, they're an empty line and not
+    // whitespace within the initial line.
+    let request_text = if config.strict_request_line {
+        request_text
+    } else {
+        request_text.trim_start_matches(|c| c == ' ' || c == '\t')
+    };
+
+    // Parse the initial line
+    let mut split_at_initial_line = request_text.splitn(2, '\n');
+    let initial_line = try!(split_at_initial_line.next()
+                                                 .ok_or(InvalidInitialLine(String::new())));
+
+    if config.strict_request_line {
+        let line = initial_line.trim_right_matches('\r');
+        let parts = line.split(' ').collect::<Vec<_>>();
+
+        if line.contains('\t') || parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+            return Err(InvalidInitialLine(initial_line.to_string()));
+        }
+    }
+
+    let (method, url, version) = try!(match initial_line.split_whitespace()
+                                                        .collect::<Vec<_>>()
+                                                        .as_slice() {
+        &[method, url, version] => {
+            let method = match classify_method_with_config(method, config) {
+                MethodRef::UNSUPPORTED(_) if config.normalize_method_case => {
+                    match classify_method_with_config(&method.to_ascii_uppercase(), config) {
+                        MethodRef::UNSUPPORTED(_) => Method::UNSUPPORTED(method.to_string()),
+                        normalized => {
+                            warnings.push(ParseWarning::MethodCaseNormalized(method.to_string()));
+                            Method::from(normalized)
+                        }
+                    }
+                }
+                classified => Method::from(classified),
+            };
+
+            Ok((method, url, try!(Request::parse_version(version))))
+        }
+        _ => Err(InvalidInitialLine(initial_line.to_string())),
+    });
+
+    if !config.allow_absolute_form && is_absolute_form(url) {
+        return Err(InvalidInitialLine(initial_line.to_string()));
+    }
+    if !config.allow_trace && method == Method::TRACE {
+        return Err(MethodNotAllowed(method.as_str().to_string()));
+    }
+    if let Some(ref accepted) = config.accepted_versions {
+        if !accepted.contains(&version) {
+            return Err(ParserError::UnsupportedVersion(version));
+        }
+    }
+
+    let remaining_request = try!(split_at_initial_line.next().ok_or(InvalidFormat));
+
+    Ok(RequestLine {
+        method: method,
+        url: url,
+        version: version,
+        warnings: warnings,
+        request_text: request_text,
+        remaining_request: remaining_request,
+    })
+}
+
+/// The second half of `parse_head`, which finds the header lines after the initial line
+pub(crate) fn split_head<'a>(line: RequestLine<'a>) -> Head<'a> {
+    let request_text = line.request_text;
+
+    // Without headers the empty line overlaps with the line ending of the initial line
+    let header_start = request_text.len() - line.remaining_request.len();
+
+    let (header_text, rest) = match find_empty_line(request_text.as_bytes(), 0) {
+        Some((headers_end, head_end)) => {
+            (&request_text[cmp::min(header_start, headers_end)..headers_end],
+             Some(&request_text[head_end..]))
+        }
+        None => (line.remaining_request, None),
+    };
+
+    Head {
+        method: line.method,
+        url: line.url,
+        version: line.version,
+        header_text: header_text,
+        rest: rest,
+        warnings: line.warnings,
+    }
+}
+
+/// Lowercases a header name the way the parser stores it, failing with
+/// `ParserError::InvalidHeader` unless the name is a token (RFC 7230 section 3.2.6), so
+/// empty and without whitespace, separators or control characters
+pub fn normalize_header_name(name: &str) -> Result<String, ParserError> {
+    let is_tchar = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+
+    if name.is_empty() || !name.chars().all(is_tchar) {
+        return Err(ParserError::InvalidHeader(name.to_string()));
+    }
+
+    Ok(name.to_ascii_lowercase())
+}
+
+/// Whether a header value can be sent as it is. A CR or LF in it would end the header
+/// line early, and anything after it would be read as headers (or a body) of its own,
+/// which is how user-controlled header values get used to split requests and responses.
+/// NUL is rejected too, since plenty of parsers treat it as the end of the value.
+pub fn is_safe_header_value(value: &str) -> bool {
+    !value.contains(|c| c == '\r' || c == '\n' || c == '\0')
+}
+
+// Whether the target is a full URL like `http://example.com/a` (RFC 7230 section 5.3.2)
+// rather than just a path: a scheme, a colon and two slashes
+pub(crate) fn is_absolute_form(url: &str) -> bool {
+    match url.find("://") {
+        Some(position) if position > 0 => {
+            let scheme = &url[..position];
+
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+            scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn check_request_line_length(bytes: &[u8],
+                                        config: &ParseConfig)
+                                        -> Result<(), ParserError> {
+    if let Some(max_length) = config.max_request_line_length {
+        let line_length = match bytes.iter().position(|&byte| byte == b'\n') {
+            Some(position) if position > 0 && bytes[position - 1] == b'\r' => position - 1,
+            Some(position) => position,
+            None => bytes.len(),
+        };
+
+        if line_length > max_length {
+            return Err(ParserError::RequestLineTooLong);
+        }
+    }
+
+    Ok(())
+}
+
+/// The length of the initial line and headers including the empty line ending them,
+/// looking for line endings from `search_start` on
+pub(crate) fn head_length(bytes: &[u8], search_start: usize) -> Option<usize> {
+    find_empty_line(bytes, search_start).map(|(_, head_end)| head_end)
+}
+
+/// Finds the empty line ending the head, returning where the line ending before it starts
+/// and where the empty line ends. Each line ending is looked at on its own, since clients
+/// mixing them may end the headers in CRLF but the empty line in a bare LF, or the other
+/// way around.
+fn find_empty_line(bytes: &[u8], mut search_start: usize) -> Option<(usize, usize)> {
+    while let Some(offset) = bytes[search_start..].iter().position(|&byte| byte == b'\n') {
+        let line_end = search_start + offset;
+        let headers_end = if line_end > 0 && bytes[line_end - 1] == b'\r' {
+            line_end - 1
+        } else {
+            line_end
+        };
+
+        match &bytes[line_end + 1..] {
+            [b'\n', ..] => return Some((headers_end, line_end + 2)),
+            [b'\r', b'\n', ..] => return Some((headers_end, line_end + 3)),
+            _ => search_start = line_end + 1,
+        }
+    }
+
+    None
+}
+
+/// Iterates over the `(name, value)` pairs of a header block without allocating, except
+/// for values folded over several lines. Names are returned as they were sent.
+pub(crate) fn header_fields<'a>(header_text: &'a str) -> HeaderFields<'a> {
+    HeaderFields { lines: header_text.lines().peekable() }
+}
+
+pub(crate) struct HeaderFields<'a> {
+    lines: Peekable<Lines<'a>>,
+}
+
+impl<'a> Iterator for HeaderFields<'a> {
+    type Item = Result<(&'a str, Cow<'a, str>), ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use self::ParserError::InvalidHeader;
+
+        while let Some(line) = self.lines.next() {
+            // If this line in a continuation of another header value ignore it
+            if line.trim_left().len() == line.len() {
+                let mut parts = line.splitn(2, ':');
+                let name = parts.next().unwrap_or("").trim_right();
+                let value = match parts.next() {
+                    Some(value) => value.trim_left(),
+                    None => return Some(Err(InvalidHeader(line.to_string()))),
+                };
+
+                let value_continuation = if let Some(next_header) = self.lines.peek() {
+                    // If the next header begins with whitespace it should be
+                    // interpreted as a continuation of the previous header's value
+                    if next_header.trim_left().len() != next_header.len() {
+                        Some(next_header.trim_left())
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+
+                let value = if let Some(value_continuation) = value_continuation {
+                    Cow::Owned(value.to_string() + " " + value_continuation)
+                } else {
+                    Cow::Borrowed(value)
+                };
+
+                return Some(Ok((name, value)));
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Cursor;
 
     const RAW_REQUEST: &'static str = "GET 	/test/1234  HTTP/1.1\nHeader1 : it\nHeader2:   \
                                        works  \n\n";
@@ -180,7 +913,7 @@ mod tests {
     #[test]
     fn parser_headers() {
         let header_text = "Header1: 1234\nHeader2 : the\n	 fox jumped";
-        let headers = Request::parse_headers(header_text).unwrap();
+        let headers = Request::parse_headers(header_text, &ParseConfig::default()).unwrap();
 
         assert_eq!(headers.get("header1"), Some(&"1234".to_string()));
         assert_eq!(headers.get("header2"), Some(&"the fox jumped".to_string()));
@@ -200,4 +933,498 @@ mod tests {
         assert!(invalid_version1.is_err());
         assert!(invalid_version2.is_err());
     }
+
+    #[test]
+    fn parse_pipelined_requests() {
+        let buffer = b"POST /first HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /second \
+                       HTTP/1.1\r\n\r\n";
+
+        let (first, consumed) = Request::from_bytes(buffer).unwrap();
+        assert_eq!(first.url, "/first");
+        assert_eq!(first.body, b"hello");
+
+        let (second, second_consumed) = Request::from_bytes(&buffer[consumed..]).unwrap();
+        assert_eq!(second.url, "/second");
+        assert_eq!(second.body, b"");
+        assert_eq!(consumed + second_consumed, buffer.len());
+
+        // The body isn't all there yet
+        assert!(match Request::from_bytes(&buffer[..consumed - 1]) {
+            Err(ParserError::Incomplete) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn validate_header_values() {
+        let config = ParseConfig { validate_header_values: true, ..ParseConfig::default() };
+
+        let vertical_tab = "GET / HTTP/1.1\nX-Test: a\u{b}b\n\n";
+        assert!(Request::from_str(vertical_tab).is_ok());
+        assert!(Request::from_str_with_config(vertical_tab, &config).is_err());
+
+        let horizontal_tab = "GET / HTTP/1.1\nX-Test: a\tb\n\n";
+        let request = Request::from_str_with_config(horizontal_tab, &config).unwrap();
+        assert_eq!(request.headers.get("x-test"), Some(&"a\tb".to_string()));
+    }
+
+    #[test]
+    fn max_total_header_bytes() {
+        let config = ParseConfig { max_total_header_bytes: Some(100), ..ParseConfig::default() };
+
+        let mut text = "GET / HTTP/1.1\r\n".to_string();
+        for i in 0..10 {
+            text.push_str(&format!("X-{}: {}\r\n", i, i));
+        }
+        assert!(Request::from_str_with_config(&(text.clone() + "\r\n"), &config).is_ok());
+
+        for i in 10..30 {
+            text.push_str(&format!("X-{}: {}\r\n", i, i));
+        }
+        match Request::from_str_with_config(&(text + "\r\n"), &config) {
+            Err(ParserError::HeadersTooLarge) => (),
+            other => panic!("expected HeadersTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mixed_empty_line() {
+        let text = "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\n\nhi";
+        let request = Request::from_str(text).unwrap();
+        assert_eq!(request.headers.get("host"), Some(&"a".to_string()));
+        assert_eq!(request.body, b"hi");
+
+        let bytes = b"GET / HTTP/1.1\r\nHost: a\r\n\nGET";
+        let (request, consumed) = Request::from_bytes(bytes).unwrap();
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(consumed, 26);
+
+        let (request, consumed) = Request::from_bytes(b"GET / HTTP/1.1\nHost: a\n\r\nGET").unwrap();
+        assert_eq!(request.headers.get("host"), Some(&"a".to_string()));
+        assert_eq!(consumed, 25);
+
+        let (_, consumed) = Request::from_bytes(b"GET / HTTP/1.1\r\n\nGET").unwrap();
+        assert_eq!(consumed, 17);
+    }
+
+    #[test]
+    fn recognize_update_method() {
+        let text = "UPDATE / HTTP/1.1\r\n\r\n";
+        assert_eq!(Request::from_str(text).unwrap().method,
+                   Method::UNSUPPORTED("UPDATE".to_string()));
+
+        let config = ParseConfig { recognize_update_method: true, ..ParseConfig::default() };
+        assert_eq!(Request::from_str_with_config(text, &config).unwrap().method,
+                   Method::UPDATE);
+    }
+
+    #[test]
+    fn allow_trace() {
+        let text = "TRACE /debug HTTP/1.1\r\nHost: a\r\n\r\n";
+        assert_eq!(Request::from_str(text).unwrap().method, Method::TRACE);
+
+        let config = ParseConfig { allow_trace: false, ..ParseConfig::default() };
+        match Request::from_str_with_config(text, &config) {
+            Err(ParserError::MethodNotAllowed(method)) => assert_eq!(method, "TRACE"),
+            other => panic!("expected MethodNotAllowed, got {:?}", other),
+        }
+        assert!(Request::from_str_with_config("GET / HTTP/1.1\r\n\r\n", &config).is_ok());
+    }
+
+    #[test]
+    fn accepted_versions() {
+        let config = ParseConfig {
+            accepted_versions: Some(vec![HttpVersion::new(1, 1)]),
+            ..ParseConfig::default()
+        };
+
+        let request = Request::from_str_with_config("GET / HTTP/1.1\r\n\r\n", &config).unwrap();
+        assert_eq!(request.version, HttpVersion::new(1, 1));
+
+        match Request::from_str_with_config("GET / HTTP/1.0\r\n\r\n", &config) {
+            Err(ParserError::UnsupportedVersion(version)) => {
+                assert_eq!(version, HttpVersion::new(1, 0))
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+        assert!(Request::from_str("GET / HTTP/1.0\r\n\r\n").is_ok());
+    }
+
+    #[test]
+    fn capture_context() {
+        let text = "GET /a HTTP/1.1\r\nNo colon here\r\n\r\n";
+        assert!(Request::from_str(text).unwrap_err().context().is_none());
+
+        let config = ParseConfig { capture_context: Some(24), ..ParseConfig::default() };
+        let err = Request::from_str_with_config(text, &config).unwrap_err();
+
+        assert_eq!(err.context(), Some("GET /a HTTP/1.1\r\nNo colo"));
+        match *err.without_context() {
+            ParserError::InvalidHeader(ref line) => assert_eq!(line, "No colon here"),
+            ref other => panic!("expected InvalidHeader, got {:?}", other),
+        }
+
+        // Never cut a character in half
+        let err = ParserError::InvalidFormat.with_context("GET /é", 6);
+        assert_eq!(err.context(), Some("GET /"));
+    }
+
+    #[test]
+    fn body_hash() {
+        let hash = |body: &str| {
+            let text = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+            Request::from_str(&text).unwrap().body_hash()
+        };
+
+        assert_eq!(hash("{\"amount\":100}"), hash("{\"amount\":100}"));
+        assert!(hash("{\"amount\":100}") != hash("{\"amount\":200}"));
+        // Fixed, so it can't change between runs
+        assert_eq!(hash("a"), Some(0xaf63dc4c8601ec8c));
+        assert_eq!(hash(""), None);
+    }
+
+    #[test]
+    fn read_length_delimited_body() {
+        let request = Request::from_str("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n").unwrap();
+        let mut conn = Cursor::new(&b"helloGET / HTTP/1.1"[..]);
+
+        assert_eq!(request.read_body(&mut conn, &ParseConfig::default()).unwrap(), b"hello");
+        assert_eq!(conn.position(), 5);
+
+        let config = ParseConfig { max_body_size: Some(4), ..ParseConfig::default() };
+        match request.read_body(&mut Cursor::new(&b"hello"[..]), &config) {
+            Err(ParserError::BodyTooLarge) => (),
+            other => panic!("expected BodyTooLarge, got {:?}", other),
+        }
+        match request.read_body(&mut Cursor::new(&b"hel"[..]), &ParseConfig::default()) {
+            Err(ParserError::Incomplete) => (),
+            other => panic!("expected Incomplete, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_chunked_body() {
+        let request = Request::from_str("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n")
+                          .unwrap();
+        let body = &b"4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\nGET / HTTP/1.1"[..];
+        let mut conn = Cursor::new(body);
+
+        assert_eq!(request.read_body(&mut conn, &ParseConfig::default()).unwrap(), b"Wikipedia");
+        assert_eq!(&body[conn.position() as usize..], b"GET / HTTP/1.1");
+
+        let config = ParseConfig { max_body_size: Some(8), ..ParseConfig::default() };
+        match request.read_body(&mut Cursor::new(body), &config) {
+            Err(ParserError::BodyTooLarge) => (),
+            other => panic!("expected BodyTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn safe_header_values() {
+        assert!(is_safe_header_value("text/html; charset=utf-8"));
+        assert!(is_safe_header_value("a\tb"));
+        assert!(!is_safe_header_value("x\r\nSet-Cookie: session=stolen"));
+        assert!(!is_safe_header_value("x\n\nbody"));
+        assert!(!is_safe_header_value("x\0"));
+    }
+
+    #[test]
+    fn leading_whitespace() {
+        let request = Request::from_str("  \tGET /a HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "/a");
+
+        let (request, consumed) = Request::from_bytes(b"  GET / HTTP/1.1\r\n\r\nGET").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(consumed, 20);
+
+        // Strictly there's no room for anything before the method
+        let config = ParseConfig { strict_request_line: true, ..ParseConfig::default() };
+        assert!(Request::from_str_with_config(" GET / HTTP/1.1\r\n\r\n", &config).is_err());
+    }
+
+    #[test]
+    fn strip_byte_order_mark() {
+        let buffer = b"\xef\xbb\xbfGET / HTTP/1.1\r\n\r\n";
+        let (request, consumed) = Request::from_bytes(buffer).unwrap();
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "/");
+        assert_eq!(consumed, buffer.len());
+    }
+
+    #[test]
+    fn content_length_leading_zeros() {
+        let text = "POST / HTTP/1.1\r\nContent-Length: 007\r\n\r\nabcdefg";
+        let request = Request::from_str(text).unwrap();
+        assert_eq!(request.content_length().unwrap(), Some(7));
+        assert_eq!(request.body, b"abcdefg");
+
+        let config = ParseConfig { canonical_content_length: true, ..ParseConfig::default() };
+        match request.content_length_with_config(&config) {
+            Err(ParserError::InvalidContentLength(value)) => assert_eq!(value, "007"),
+            other => panic!("expected InvalidContentLength, got {:?}", other),
+        }
+        assert!(Request::from_str_with_config(text, &config).is_err());
+        assert!(Request::from_bytes_with_config(text.as_bytes(), &config).is_err());
+
+        let text = "POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\n";
+        let request = Request::from_str_with_config(text, &config).unwrap();
+        assert_eq!(request.content_length_with_config(&config).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn strict_content_length() {
+        let mut request = Request::from_str("POST / HTTP/1.1\nContent-Length: 5\n\nhello").unwrap();
+        assert_eq!(request.content_length().unwrap(), Some(5));
+
+        for malformed in &[" 5", "5 ", "+5", "0x5", "5, 5", "-1", "", "99999999999999999999999"] {
+            request.headers.insert("content-length".to_string(), malformed.to_string());
+            assert!(request.content_length().is_err(), "accepted {:?}", malformed);
+        }
+
+        // Parsing fails too, since the body can't be framed
+        assert!(Request::from_str("POST / HTTP/1.1\nContent-Length: 5 \n\nhello").is_err());
+    }
+
+    #[test]
+    fn repeated_content_length() {
+        let text = "POST / HTTP/1.1\nContent-Length: 5\nContent-Length: 5\n\nhello";
+        assert_eq!(Request::from_str(text).unwrap().content_length().unwrap(), Some(5));
+
+        let text = "POST / HTTP/1.1\nContent-Length: 5\nContent-Length: 50\n\nhello";
+        match Request::from_str(text) {
+            Err(ParserError::InvalidContentLength(values)) => assert_eq!(values, "5, 50"),
+            other => panic!("expected InvalidContentLength, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pipeline_safety() {
+        let get = Request::from_str("GET / HTTP/1.1\nHost: a\n\n").unwrap();
+        assert!(get.is_pipeline_safe());
+
+        let post = Request::from_str("POST / HTTP/1.1\nContent-Length: 2\n\nhi").unwrap();
+        assert!(!post.is_pipeline_safe());
+
+        let chunked = Request::from_str("POST / HTTP/1.1\nTransfer-Encoding: chunked\n\n").unwrap();
+        assert!(!chunked.is_pipeline_safe());
+    }
+
+    #[test]
+    fn max_request_line_length() {
+        let config = ParseConfig { max_request_line_length: Some(32), ..ParseConfig::default() };
+
+        assert!(Request::from_str_with_config("GET /short HTTP/1.1\r\n\r\n", &config).is_ok());
+
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(100));
+        assert!(match Request::from_str_with_config(&long_line, &config) {
+            Err(ParserError::RequestLineTooLong) => true,
+            _ => false,
+        });
+
+        // Even before the rest of the request has arrived
+        assert!(match Request::from_bytes_with_config(&long_line.as_bytes()[..50], &config) {
+            Err(ParserError::RequestLineTooLong) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn method_names() {
+        assert_eq!(<&'static str>::try_from(&Method::GET), Ok("GET"));
+        assert_eq!(<&'static str>::try_from(&Method::UNSUPPORTED("BREW".to_string())),
+                   Err("BREW"));
+        assert_eq!(Method::UNSUPPORTED("BREW".to_string()).as_str(), "BREW");
+    }
+
+    #[test]
+    fn strict_request_line() {
+        let config = ParseConfig { strict_request_line: true, ..ParseConfig::default() };
+
+        assert!(Request::from_str_with_config("GET /test HTTP/1.1\r\n\r\n", &config).is_ok());
+        assert!(match Request::from_str_with_config(RAW_REQUEST, &config) {
+            Err(ParserError::InvalidInitialLine(_)) => true,
+            _ => false,
+        });
+        assert!(Request::from_str_with_config(" GET /test HTTP/1.1\n\n", &config).is_err());
+    }
+
+    #[test]
+    fn classify_methods() {
+        assert_eq!(classify_method("GET"), MethodRef::GET);
+        assert_eq!(Method::from(classify_method("DELETE")), Method::DELETE);
+
+        let garbage = "\u{1}\u{2}XQZ";
+        match classify_method(garbage) {
+            // Pointing into the input means nothing was copied
+            MethodRef::UNSUPPORTED(name) => assert_eq!(name.as_ptr(), garbage.as_ptr()),
+            other => panic!("expected UNSUPPORTED, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn absolute_form() {
+        let proxied = "GET http://example.com/a?b=c HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert_eq!(Request::from_str(proxied).unwrap().url, "http://example.com/a?b=c");
+
+        let config = ParseConfig { allow_absolute_form: false, ..ParseConfig::default() };
+        assert!(match Request::from_str_with_config(proxied, &config) {
+            Err(ParserError::InvalidInitialLine(_)) => true,
+            _ => false,
+        });
+        assert!(Request::from_str_with_config("GET /a?next=http://b/ HTTP/1.1\r\n\r\n", &config)
+                    .is_ok());
+        assert!(Request::from_str_with_config("OPTIONS * HTTP/1.1\r\n\r\n", &config).is_ok());
+    }
+
+    #[test]
+    fn max_total_bytes() {
+        let config = ParseConfig { max_total_bytes: Some(64), ..ParseConfig::default() };
+        let request = |text: &str| {
+            Request::from_bytes_with_config(text.as_bytes(), &config).map(|(_, length)| length)
+        };
+
+        assert!(request("POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody").is_ok());
+        assert!(match request("POST / HTTP/1.1\r\nContent-Length: 60\r\n\r\n") {
+            Err(ParserError::BodyTooLarge) => true,
+            _ => false,
+        });
+
+        // Chunked bodies don't say how large they get, they fail once they grow too much
+        let chunked = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n10\r\n";
+        assert!(match request(chunked) {
+            Err(ParserError::Incomplete) => true,
+            _ => false,
+        });
+        assert!(match request(&format!("{}0123456789abcdef\r\n10\r\n", chunked)) {
+            Err(ParserError::BodyTooLarge) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn body_completeness() {
+        let complete = Request::from_str("POST / HTTP/1.1\nContent-Length: 2\n\nhi").unwrap();
+        assert!(complete.is_body_complete());
+
+        let truncated = Request::from_str("POST / HTTP/1.1\nContent-Length: 5\n\nhi").unwrap();
+        assert!(!truncated.is_body_complete());
+
+        let chunked = Request::from_str("POST / HTTP/1.1\nTransfer-Encoding: chunked\n\n2\r\nhi")
+                          .unwrap();
+        assert!(!chunked.is_body_complete());
+
+        let (pipelined, _) = Request::from_bytes(b"POST / HTTP/1.1\nContent-Length: 2\n\nhiGET")
+                                 .unwrap();
+        assert!(pipelined.is_body_complete());
+
+        let get = Request::from_str("GET / HTTP/1.1\n\n").unwrap();
+        assert!(get.is_body_complete());
+    }
+
+    #[test]
+    fn normalize_method_case() {
+        assert_eq!(Request::from_str("get / HTTP/1.1\r\n\r\n").unwrap().method,
+                   Method::UNSUPPORTED("get".to_string()));
+
+        let config = ParseConfig { normalize_method_case: true, ..ParseConfig::default() };
+        let (request, warnings) = Request::from_str_with_warnings("get / HTTP/1.1\r\n\r\n",
+                                                                  &config)
+                                      .unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(warnings, vec![ParseWarning::MethodCaseNormalized("get".to_string())]);
+
+        let (request, warnings) = Request::from_str_with_warnings("brew / HTTP/1.1\r\n\r\n",
+                                                                  &config)
+                                      .unwrap();
+        assert_eq!(request.method, Method::UNSUPPORTED("brew".to_string()));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn header_names() {
+        assert_eq!(normalize_header_name("X-Request-ID").unwrap(), "x-request-id");
+        assert_eq!(normalize_header_name("a!#$%&'*+-.^_`|~9").unwrap(), "a!#$%&'*+-.^_`|~9");
+
+        for name in &["", "Bad Name", "Host:", "x(y)", "caf\u{e9}", "a\tb"] {
+            assert!(match normalize_header_name(name) {
+                Err(ParserError::InvalidHeader(_)) => true,
+                _ => false,
+            });
+        }
+
+        let config = ParseConfig { validate_header_values: true, ..ParseConfig::default() };
+        assert!(Request::from_str_with_config("GET / HTTP/1.1\r\nBad Name: x\r\n\r\n", &config)
+                    .is_err());
+    }
+
+    #[test]
+    fn unexpected_body() {
+        let get = Request::from_str("GET / HTTP/1.1\nContent-Length: 2\n\nhi").unwrap();
+        assert!(get.has_unexpected_body());
+
+        let head = Request::from_str("HEAD / HTTP/1.1\nTransfer-Encoding: chunked\n\n").unwrap();
+        assert!(head.has_unexpected_body());
+
+        let post = Request::from_str("POST / HTTP/1.1\nContent-Length: 2\n\nhi").unwrap();
+        assert!(!post.has_unexpected_body());
+
+        let empty = Request::from_str("GET / HTTP/1.1\nContent-Length: 0\n\n").unwrap();
+        assert!(!empty.has_unexpected_body());
+    }
+
+    #[test]
+    fn transfer_encoding_chain() {
+        let request = Request::from_str("POST / HTTP/1.1\r\nTransfer-Encoding: gzip, \
+                                         Chunked\r\n\r\n0\r\n\r\n")
+                          .unwrap();
+        assert_eq!(request.transfer_encodings(), vec!["gzip", "Chunked"]);
+        assert!(request.is_chunked());
+
+        for encoding in &["chunked, gzip", "gzip"] {
+            let text = format!("POST / HTTP/1.1\r\nTransfer-Encoding: {}\r\n\r\n0\r\n\r\n",
+                               encoding);
+
+            assert!(match Request::from_str(&text) {
+                Err(ParserError::ConflictingBodyFraming) => true,
+                _ => false,
+            });
+            assert!(match Request::from_bytes(text.as_bytes()) {
+                Err(ParserError::ConflictingBodyFraming) => true,
+                _ => false,
+            });
+        }
+    }
+
+    #[test]
+    fn serialize() {
+        let text = "POST /a HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\nhi";
+        let request = Request::from_str(text).unwrap();
+
+        assert_eq!(request.to_bytes(),
+                   &b"POST /a HTTP/1.1\r\ncontent-length: 2\r\nhost: x\r\n\r\nhi"[..]);
+        let bytes = request.to_bytes();
+        let (reparsed, _) = Request::from_bytes(&bytes).unwrap();
+        assert_eq!(reparsed.headers, request.headers);
+        assert_eq!(reparsed.body, request.body);
+    }
+
+    #[test]
+    fn method_override() {
+        let text = "POST /items/1 HTTP/1.1\r\nX-HTTP-Method-Override: DELETE\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+        let config = ParseConfig { allow_method_override: true, ..ParseConfig::default() };
+
+        assert_eq!(request.effective_method(&config), Method::DELETE);
+        assert_eq!(request.effective_method(&ParseConfig::default()), Method::POST);
+
+        let text = "GET /items/1 HTTP/1.1\r\nX-HTTP-Method-Override: DELETE\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+        assert_eq!(request.effective_method(&config), Method::GET);
+
+        let text = "POST / HTTP/1.1\r\nX-HTTP-Method-Override: BREW\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+        assert_eq!(request.effective_method(&config), Method::POST);
+    }
 }
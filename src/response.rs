@@ -0,0 +1,481 @@
+use std::fmt;
+use std::io::{self, Write};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use date::HttpDate;
+use headers::split_list_header;
+use request::{is_safe_header_value, HttpVersion, Method, ParserError};
+
+macro_rules! status_codes {
+    ($(($code:expr, $variant:ident, $reason:expr),)+) => {
+        #[derive(Eq, PartialEq, Debug, Clone, Copy)]
+        pub enum StatusCode {
+            $($variant,)+
+            UNKNOWN(u16),
+        }
+
+        impl StatusCode {
+            pub fn from_u16(code: u16) -> StatusCode {
+                match code {
+                    $($code => StatusCode::$variant,)+
+                    _ => StatusCode::UNKNOWN(code),
+                }
+            }
+
+            pub fn as_u16(&self) -> u16 {
+                match *self {
+                    $(StatusCode::$variant => $code,)+
+                    StatusCode::UNKNOWN(code) => code,
+                }
+            }
+
+            /// The canonical reason phrase, or an empty string for codes we don't know
+            pub fn reason_phrase(&self) -> &'static str {
+                match *self {
+                    $(StatusCode::$variant => $reason,)+
+                    StatusCode::UNKNOWN(_) => "",
+                }
+            }
+        }
+    }
+}
+
+status_codes! {
+    (100, Continue, "Continue"),
+    (101, SwitchingProtocols, "Switching Protocols"),
+    (103, EarlyHints, "Early Hints"),
+    (200, Ok, "OK"),
+    (201, Created, "Created"),
+    (202, Accepted, "Accepted"),
+    (203, NonAuthoritativeInformation, "Non-Authoritative Information"),
+    (204, NoContent, "No Content"),
+    (205, ResetContent, "Reset Content"),
+    (206, PartialContent, "Partial Content"),
+    (300, MultipleChoices, "Multiple Choices"),
+    (301, MovedPermanently, "Moved Permanently"),
+    (302, Found, "Found"),
+    (303, SeeOther, "See Other"),
+    (304, NotModified, "Not Modified"),
+    (307, TemporaryRedirect, "Temporary Redirect"),
+    (308, PermanentRedirect, "Permanent Redirect"),
+    (400, BadRequest, "Bad Request"),
+    (401, Unauthorized, "Unauthorized"),
+    (402, PaymentRequired, "Payment Required"),
+    (403, Forbidden, "Forbidden"),
+    (404, NotFound, "Not Found"),
+    (405, MethodNotAllowed, "Method Not Allowed"),
+    (406, NotAcceptable, "Not Acceptable"),
+    (407, ProxyAuthenticationRequired, "Proxy Authentication Required"),
+    (408, RequestTimeout, "Request Timeout"),
+    (409, Conflict, "Conflict"),
+    (410, Gone, "Gone"),
+    (411, LengthRequired, "Length Required"),
+    (412, PreconditionFailed, "Precondition Failed"),
+    (413, PayloadTooLarge, "Payload Too Large"),
+    (414, UriTooLong, "URI Too Long"),
+    (415, UnsupportedMediaType, "Unsupported Media Type"),
+    (416, RangeNotSatisfiable, "Range Not Satisfiable"),
+    (417, ExpectationFailed, "Expectation Failed"),
+    (421, MisdirectedRequest, "Misdirected Request"),
+    (422, UnprocessableEntity, "Unprocessable Entity"),
+    (425, TooEarly, "Too Early"),
+    (426, UpgradeRequired, "Upgrade Required"),
+    (428, PreconditionRequired, "Precondition Required"),
+    (429, TooManyRequests, "Too Many Requests"),
+    (431, RequestHeaderFieldsTooLarge, "Request Header Fields Too Large"),
+    (451, UnavailableForLegalReasons, "Unavailable For Legal Reasons"),
+    (500, InternalServerError, "Internal Server Error"),
+    (501, NotImplemented, "Not Implemented"),
+    (502, BadGateway, "Bad Gateway"),
+    (503, ServiceUnavailable, "Service Unavailable"),
+    (504, GatewayTimeout, "Gateway Timeout"),
+    (505, HttpVersionNotSupported, "HTTP Version Not Supported"),
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.as_u16(), self.reason_phrase())
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct Response {
+    pub version: HttpVersion,
+    pub status: StatusCode,
+
+    // Unlike request headers these are kept as a list, since responses may legitimately
+    // repeat a header (Link, Set-Cookie). Names keep the casing they were added with.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: StatusCode) -> Response {
+        Response {
+            version: HttpVersion::new(1, 1),
+            status: status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Builds a plain text response whose body is the status' reason phrase, which is all
+    /// most error responses need: `Response::from_status(404)`
+    pub fn from_status(status: u16) -> Response {
+        let mut response = Response::new(StatusCode::from_u16(status));
+        response.body = response.status.reason_phrase().as_bytes().to_vec();
+
+        let length = response.body.len().to_string();
+        response.add_header("Content-Type", "text/plain");
+        response.add_header("Content-Length", &length);
+        response
+    }
+
+    /// Builds an `application/json` response from anything serde can serialize
+    #[cfg(feature = "serde")]
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Result<Response, ParserError> {
+        let mut response = Response::new(StatusCode::from_u16(status));
+        response.body = try!(serde_json::to_vec(value)
+                                 .map_err(|err| ParserError::Serialization(err.to_string())));
+
+        let length = response.body.len().to_string();
+        response.add_header("Content-Type", "application/json");
+        response.add_header("Content-Length", &length);
+        Ok(response)
+    }
+
+    /// Builds a `103 Early Hints` response carrying one `Link` header per link
+    pub fn early_hints(links: &[&str]) -> Response {
+        let mut response = Response::new(StatusCode::EarlyHints);
+
+        for link in links {
+            response.add_header("Link", link);
+        }
+
+        response
+    }
+
+    /// Builds a `401 Unauthorized` response challenging the client with the given
+    /// `WWW-Authenticate` value, e.g. `Basic realm="api"`
+    pub fn unauthorized(challenge: &str) -> Response {
+        let mut response = Response::new(StatusCode::Unauthorized);
+        response.add_header("WWW-Authenticate", challenge);
+        response
+    }
+
+    /// Builds the `204 No Content` answer to a CORS preflight request, allowing the origin
+    /// to make requests with the given methods and headers. Unless the origin is `*` the
+    /// response also varies by Origin, so caches don't hand it to other origins.
+    pub fn cors_preflight(allowed_methods: &[Method],
+                          allowed_headers: &[&str],
+                          origin: &str)
+                          -> Response {
+        let methods = allowed_methods.iter().map(Method::as_str).collect::<Vec<_>>();
+
+        let mut response = Response::new(StatusCode::NoContent);
+        response.add_header("Access-Control-Allow-Origin", origin);
+        response.add_header("Access-Control-Allow-Methods", &methods.join(", "));
+        response.add_header("Access-Control-Allow-Headers", &allowed_headers.join(", "));
+        if origin != "*" {
+            response.add_header("Vary", "Origin");
+        }
+        response
+    }
+
+    /// Returns the first value of the header, matching the name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|&&(ref header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_str())
+    }
+
+    /// Returns every value of the header in the order they were added
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|&&(ref header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_str())
+            .collect()
+    }
+
+    /// Appends a header, keeping any existing values of the same name. The value is sent
+    /// as is, so one that comes from the request should go through `try_add_header`.
+    pub fn add_header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Replaces all existing values of the header with a single value. Like with
+    /// `add_header`, the value isn't checked, see `try_set_header`.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|&(ref header_name, _)| !header_name.eq_ignore_ascii_case(name));
+        self.add_header(name, value);
+    }
+
+    /// Appends a header like `add_header`, unless the value would end the header line
+    /// early and split the response (see `is_safe_header_value`), which is a
+    /// `ParserError::InvalidHeader`
+    pub fn try_add_header(&mut self, name: &str, value: &str) -> Result<(), ParserError> {
+        if !is_safe_header_value(value) {
+            return Err(ParserError::InvalidHeader(name.to_string()));
+        }
+        self.add_header(name, value);
+        Ok(())
+    }
+
+    /// Replaces the header like `set_header`, with the check of `try_add_header`
+    pub fn try_set_header(&mut self, name: &str, value: &str) -> Result<(), ParserError> {
+        if !is_safe_header_value(value) {
+            return Err(ParserError::InvalidHeader(name.to_string()));
+        }
+        self.set_header(name, value);
+        Ok(())
+    }
+
+    /// Sets the Date header, which every response from a server with a clock should have
+    pub fn set_date(&mut self, date: HttpDate) {
+        self.set_header("Date", &date.to_string());
+    }
+
+    /// Sets the Date header to the current time
+    pub fn with_date_now(&mut self) {
+        self.set_date(HttpDate::now());
+    }
+
+    /// Serializes the status line, headers and body into their HTTP/1 wire form
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    fn head_bytes(&self) -> Vec<u8> {
+        let mut head = format!("{} {}\r\n", self.version, self.status);
+
+        for &(ref name, ref value) in &self.headers {
+            head.push_str(name);
+            head.push_str(": ");
+            head.push_str(value);
+            head.push_str("\r\n");
+        }
+        head.push_str("\r\n");
+
+        head.into_bytes()
+    }
+}
+
+/// Writes a response with a chunked body of unknown length, one chunk per `write_chunk`
+/// call. The body has to be ended with `finish` or `write_trailers`.
+pub struct ChunkedResponseWriter<W: Write> {
+    writer: W,
+    // Lowercased names of the Trailer header
+    trailer_names: Vec<String>,
+}
+
+impl<W: Write> ChunkedResponseWriter<W> {
+    /// Writes the status line and headers of the response, which get a
+    /// `Transfer-Encoding: chunked` in place of any Content-Length. Its body is ignored.
+    pub fn new(mut writer: W, mut response: Response) -> io::Result<ChunkedResponseWriter<W>> {
+        response.headers.retain(|&(ref name, _)| !name.eq_ignore_ascii_case("content-length"));
+        response.set_header("Transfer-Encoding", "chunked");
+        try!(writer.write_all(&response.head_bytes()));
+
+        let trailer_names = response.header_values("trailer")
+                                    .iter()
+                                    .flat_map(|value| split_list_header(value))
+                                    .map(|name| name.to_lowercase())
+                                    .collect();
+
+        Ok(ChunkedResponseWriter {
+            writer: writer,
+            trailer_names: trailer_names,
+        })
+    }
+
+    /// Writes the data as one chunk. Nothing is written for empty data, as an empty chunk
+    /// would end the body.
+    pub fn write_chunk(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        try!(write!(self.writer, "{:x}\r\n", data.len()));
+        try!(self.writer.write_all(data));
+        self.writer.write_all(b"\r\n")
+    }
+
+    /// Ends the body with the last chunk and returns the writer
+    pub fn finish(self) -> io::Result<W> {
+        self.write_trailers(&[])
+    }
+
+    /// Ends the body with the last chunk followed by trailer fields. Every trailer has to
+    /// be announced in the response's Trailer header and have a value that is safe to send
+    /// (see `is_safe_header_value`), otherwise nothing is written and this fails with
+    /// `InvalidInput`.
+    ///
+    /// This takes the writer by value rather than `&mut self`, as nothing can be written
+    /// after the trailers, and hands back the underlying writer like `finish`.
+    pub fn write_trailers(mut self, trailers: &[(String, String)]) -> io::Result<W> {
+        for &(ref name, ref value) in trailers {
+            if !self.trailer_names.contains(&name.to_lowercase()) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("trailer {} missing from the Trailer header",
+                                                  name)));
+            }
+            if !is_safe_header_value(value) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("trailer {} has an unsafe value", name)));
+            }
+        }
+
+        let mut last_chunk = "0\r\n".to_string();
+        for &(ref name, ref value) in trailers {
+            last_chunk.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        last_chunk.push_str("\r\n");
+
+        try!(self.writer.write_all(last_chunk.as_bytes()));
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_code_table() {
+        assert_eq!(StatusCode::from_u16(103), StatusCode::EarlyHints);
+        assert_eq!(StatusCode::EarlyHints.as_u16(), 103);
+        assert_eq!(StatusCode::EarlyHints.reason_phrase(), "Early Hints");
+        assert_eq!(StatusCode::from_u16(599), StatusCode::UNKNOWN(599));
+    }
+
+    #[test]
+    fn early_hints() {
+        let response = Response::early_hints(&["</style.css>; rel=preload; as=style",
+                                               "</script.js>; rel=preload; as=script"]);
+
+        assert_eq!(response.status, StatusCode::EarlyHints);
+        assert_eq!(response.header_values("link"),
+                   vec!["</style.css>; rel=preload; as=style",
+                        "</script.js>; rel=preload; as=script"]);
+        assert_eq!(String::from_utf8(response.to_bytes()).unwrap(),
+                   "HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload; \
+                    as=style\r\nLink: </script.js>; rel=preload; as=script\r\n\r\n");
+    }
+
+    #[test]
+    fn unauthorized() {
+        let response = Response::unauthorized("Basic realm=\"api\"");
+
+        assert_eq!(response.status.as_u16(), 401);
+        assert_eq!(response.header("www-authenticate"), Some("Basic realm=\"api\""));
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn cors_preflight() {
+        let response = Response::cors_preflight(&[Method::GET, Method::PUT],
+                                                &["Content-Type", "X-Request-ID"],
+                                                "https://app.example.com");
+
+        assert_eq!(response.status, StatusCode::NoContent);
+        assert_eq!(response.header("access-control-allow-origin"),
+                   Some("https://app.example.com"));
+        assert_eq!(response.header("access-control-allow-methods"), Some("GET, PUT"));
+        assert_eq!(response.header("access-control-allow-headers"),
+                   Some("Content-Type, X-Request-ID"));
+        assert_eq!(response.header("vary"), Some("Origin"));
+    }
+
+    #[test]
+    fn from_status() {
+        let response = Response::from_status(404);
+
+        assert_eq!(response.status, StatusCode::NotFound);
+        assert_eq!(response.header("content-type"), Some("text/plain"));
+        assert_eq!(response.header("content-length"), Some("9"));
+        assert_eq!(response.body, b"Not Found");
+    }
+
+    #[test]
+    fn reject_unsafe_header_values() {
+        let mut response = Response::new(StatusCode::Ok);
+        response.try_add_header("X-Echo", "hello").unwrap();
+
+        let echoed = "x\r\nSet-Cookie: session=stolen";
+        match response.try_add_header("X-Echo", echoed) {
+            Err(ParserError::InvalidHeader(name)) => assert_eq!(name, "X-Echo"),
+            other => panic!("expected InvalidHeader, got {:?}", other),
+        }
+        assert!(response.try_set_header("X-Echo", echoed).is_err());
+        assert_eq!(response.header_values("x-echo"), vec!["hello"]);
+
+        response.try_set_header("X-Echo", "again").unwrap();
+        assert_eq!(response.header_values("x-echo"), vec!["again"]);
+    }
+
+    #[test]
+    fn date_header() {
+        let mut response = Response::new(StatusCode::Ok);
+        response.set_date(HttpDate::from_timestamp(784111777));
+        assert_eq!(response.header("date"), Some("Sun, 06 Nov 1994 08:49:37 GMT"));
+
+        response.with_date_now();
+        assert_eq!(response.header_values("date").len(), 1);
+        assert!(HttpDate::parse(response.header("date").unwrap()).is_some());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        #[derive(Serialize)]
+        struct User {
+            id: u32,
+            name: &'static str,
+        }
+
+        let response = Response::json(201, &User { id: 7, name: "ada" }).unwrap();
+
+        assert_eq!(response.status, StatusCode::Created);
+        assert_eq!(response.body, b"{\"id\":7,\"name\":\"ada\"}");
+        assert_eq!(response.header("content-type"), Some("application/json"));
+        assert_eq!(response.header("content-length"), Some("21"));
+    }
+
+    #[test]
+    fn chunked_trailers() {
+        let mut response = Response::new(StatusCode::Ok);
+        response.add_header("Trailer", "Server-Timing, X-Checksum");
+        response.add_header("Content-Length", "100");
+
+        let mut writer = ChunkedResponseWriter::new(Vec::new(), response).unwrap();
+        writer.write_chunk(b"hello ").unwrap();
+        writer.write_chunk(b"").unwrap();
+        writer.write_chunk(b"chunked world").unwrap();
+        let output = writer.write_trailers(&[("x-checksum".to_string(), "abc".to_string())])
+                           .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(),
+                   "HTTP/1.1 200 OK\r\nTrailer: Server-Timing, X-Checksum\r\nTransfer-Encoding: \
+                    chunked\r\n\r\n6\r\nhello \r\nd\r\nchunked world\r\n0\r\nx-checksum: \
+                    abc\r\n\r\n");
+
+        let writer = ChunkedResponseWriter::new(Vec::new(), Response::new(StatusCode::Ok)).unwrap();
+        let err = writer.write_trailers(&[("X-Checksum".to_string(), "abc".to_string())])
+                        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut response = Response::new(StatusCode::Ok);
+        response.add_header("Trailer", "X-Checksum");
+        let writer = ChunkedResponseWriter::new(Vec::new(), response).unwrap();
+        let err = writer.write_trailers(&[("X-Checksum".to_string(),
+                                           "abc\r\n\r\nHTTP/1.1 200 OK".to_string())])
+                        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
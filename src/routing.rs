@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use request::{Method, Request};
+use response::{Response, StatusCode};
+use url::percent_decode;
+
+pub type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+struct Route {
+    method: Method,
+    pattern: String,
+    handler: Box<Handler>,
+}
+
+/// Dispatches requests to the handler of the first route matching their method and path,
+/// using the same patterns as `Request::matches`
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router { routes: Vec::new() }
+    }
+
+    pub fn route<H>(mut self, method: Method, pattern: &str, handler: H) -> Router
+        where H: Fn(&Request) -> Response + Send + Sync + 'static
+    {
+        self.routes.push(Route {
+            method: method,
+            pattern: pattern.to_string(),
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Calls the matching handler, or responds with `404 Not Found` if there is none
+    pub fn dispatch(&self, request: &Request) -> Response {
+        let path = request.path();
+
+        for route in &self.routes {
+            if route.method == request.method && match_path(&route.pattern, path).is_some() {
+                return (route.handler)(request);
+            }
+        }
+
+        Response::new(StatusCode::NotFound)
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
+    }
+}
+
+impl<'a> Request<'a> {
+    /// Matches the method and path against a simple pattern, where a `*` segment matches
+    /// any single path segment and a `**` segment matches the rest of the path.
+    /// `/users/*` matches `/users/5` but not `/users/5/posts`, while `/static/**` matches
+    /// any path below `/static`.
+    pub fn matches(&self, method: Method, pattern: &str) -> bool {
+        self.method == method && match_path(pattern, self.path()).is_some()
+    }
+
+    /// Like `matches` but only for the path, additionally capturing the (percent-decoded)
+    /// path segment matched by each `:name` segment of the pattern. `/users/:id` against
+    /// `/users/42` captures `id` as `42`.
+    pub fn capture(&self, pattern: &str) -> Option<HashMap<String, String>> {
+        match_path(pattern, self.path())
+    }
+}
+
+fn match_path(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let mut captures = HashMap::new();
+    let mut path_segments = path.split('/');
+
+    for pattern_segment in pattern.split('/') {
+        if pattern_segment == "**" {
+            return Some(captures);
+        }
+
+        let path_segment = try_opt!(path_segments.next());
+
+        if pattern_segment == "*" || pattern_segment.starts_with(':') {
+            if path_segment.is_empty() {
+                return None;
+            }
+
+            if pattern_segment.starts_with(':') {
+                captures.insert(pattern_segment[1..].to_string(), percent_decode(path_segment));
+            }
+        } else if pattern_segment != path_segment {
+            return None;
+        }
+    }
+
+    if path_segments.next().is_none() {
+        Some(captures)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_segment_wildcard() {
+        let request = Request::from_str("GET /users/5 HTTP/1.1\n\n").unwrap();
+        assert!(request.matches(Method::GET, "/users/*"));
+        assert!(!request.matches(Method::POST, "/users/*"));
+        assert!(!request.matches(Method::GET, "/users"));
+
+        let nested = Request::from_str("GET /users/5/posts HTTP/1.1\n\n").unwrap();
+        assert!(!nested.matches(Method::GET, "/users/*"));
+    }
+
+    #[test]
+    fn rest_of_path_wildcard() {
+        let request = Request::from_str("GET /static/css/site.css?v=2 HTTP/1.1\n\n").unwrap();
+        assert!(request.matches(Method::GET, "/static/**"));
+        assert!(request.matches(Method::GET, "/**"));
+        assert!(!request.matches(Method::GET, "/assets/**"));
+    }
+
+    #[test]
+    fn capture_single() {
+        let request = Request::from_str("GET /users/42 HTTP/1.1\n\n").unwrap();
+        let captures = request.capture("/users/:id").unwrap();
+
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures.get("id"), Some(&"42".to_string()));
+        assert_eq!(request.capture("/posts/:id"), None);
+    }
+
+    #[test]
+    fn capture_multiple() {
+        let request = Request::from_str("GET /users/ada%20l/posts/7 HTTP/1.1\n\n").unwrap();
+        let captures = request.capture("/users/:name/posts/:post").unwrap();
+
+        assert_eq!(captures.get("name"), Some(&"ada l".to_string()));
+        assert_eq!(captures.get("post"), Some(&"7".to_string()));
+        assert_eq!(request.capture("/users/:name"), None);
+    }
+
+    #[test]
+    fn router_dispatch() {
+        let router = Router::new()
+                         .route(Method::GET, "/users/*", |_| Response::new(StatusCode::Ok))
+                         .route(Method::POST, "/users", |_| Response::new(StatusCode::Created));
+
+        let get = Request::from_str("GET /users/5 HTTP/1.1\n\n").unwrap();
+        assert_eq!(router.dispatch(&get).status, StatusCode::Ok);
+
+        let post = Request::from_str("POST /users HTTP/1.1\n\n").unwrap();
+        assert_eq!(router.dispatch(&post).status, StatusCode::Created);
+
+        let missing = Request::from_str("DELETE /users/5 HTTP/1.1\n\n").unwrap();
+        assert_eq!(router.dispatch(&missing).status, StatusCode::NotFound);
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use config::ParseConfig;
+use incremental::{ParseStep, ParseState};
+use request::{Method, ParserError, Request};
+use response::{Response, StatusCode};
+use routing::Router;
+
+/// A minimal HTTP/1 server handling connections on a fixed pool of threads:
+///
+/// ```no_run
+/// use http2::request::Method;
+/// use http2::response::{Response, StatusCode};
+/// use http2::server::Server;
+///
+/// Server::bind("127.0.0.1:8080").unwrap()
+///     .route(Method::GET, "/", |_| Response::new(StatusCode::Ok))
+///     .run()
+///     .unwrap();
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    router: Router,
+    threads: usize,
+    config: ParseConfig,
+    timeout: Duration,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Server> {
+        Ok(Server {
+            listener: try!(TcpListener::bind(addr)),
+            router: Router::new(),
+            threads: 4,
+            config: ParseConfig {
+                max_request_line_length: Some(8 * 1024),
+                max_total_bytes: Some(1024 * 1024),
+                ..ParseConfig::default()
+            },
+            timeout: Duration::from_secs(30),
+        })
+    }
+
+    pub fn route<H>(mut self, method: Method, pattern: &str, handler: H) -> Server
+        where H: Fn(&Request) -> Response + Send + Sync + 'static
+    {
+        self.router = self.router.route(method, pattern, handler);
+        self
+    }
+
+    /// How many connections are handled at the same time
+    pub fn threads(mut self, threads: usize) -> Server {
+        self.threads = threads;
+        self
+    }
+
+    /// How requests are parsed. By default a request line may be 8 KiB and a whole request
+    /// 1 MiB, as a server without limits buffers whatever a client sends it.
+    pub fn config(mut self, config: ParseConfig) -> Server {
+        self.config = config;
+        self
+    }
+
+    /// How long reading from or writing to a connection may take before it's closed, 30
+    /// seconds by default. Idle keep-alive connections are closed after it too, otherwise
+    /// a few clients doing nothing would tie up every thread, or sooner if the client
+    /// asks for a shorter `Keep-Alive: timeout=`.
+    pub fn timeout(mut self, timeout: Duration) -> Server {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections forever. Failing to accept one, like when the process is out of
+    /// file descriptors for a moment, only loses that connection.
+    pub fn run(self) -> io::Result<()> {
+        let router = Arc::new(self.router);
+        let config = Arc::new(self.config);
+        let timeout = self.timeout;
+        let (sender, receiver) = mpsc::channel::<TcpStream>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..self.threads {
+            let router = router.clone();
+            let config = config.clone();
+            let receiver = receiver.clone();
+
+            thread::spawn(move || {
+                loop {
+                    let stream = match receiver.lock().unwrap().recv() {
+                        Ok(stream) => stream,
+                        Err(_) => return,
+                    };
+
+                    // Neither a failing connection nor a panicking handler should take the
+                    // worker down with it. The connection is dropped either way.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                        handle_connection(stream, &router, &config, timeout)
+                    }));
+                }
+            });
+        }
+
+        for stream in self.listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // Workers never stop, see above
+                    sender.send(stream).expect("all server threads have stopped")
+                }
+                // Most accept errors pass, and retrying right away when out of file
+                // descriptors would only spin
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn handle_connection(mut stream: TcpStream,
+                     router: &Router,
+                     config: &ParseConfig,
+                     timeout: Duration)
+                     -> io::Result<()> {
+    try!(stream.set_read_timeout(Some(timeout)));
+    try!(stream.set_write_timeout(Some(timeout)));
+
+    let mut state = ParseState::with_config(config.clone());
+    let mut buffer = [0u8; 4096];
+    // Whether the read timeout is the one negotiated for waiting between requests
+    let mut idle_timeout = false;
+
+    loop {
+        let read = try!(stream.read(&mut buffer));
+        if read == 0 {
+            return Ok(());
+        }
+        // The next request has started, which gets the full timeout again
+        if idle_timeout {
+            try!(stream.set_read_timeout(Some(timeout)));
+            idle_timeout = false;
+        }
+
+        // Keep going while the buffer holds more pipelined requests
+        let mut bytes = &buffer[..read];
+        loop {
+            let (response, keep_alive) = match state.parse_more(bytes) {
+                ParseStep::NeedMore => break,
+                ParseStep::Complete(request) => {
+                    (router.dispatch(&request), request.effective_keep_alive_timeout(timeout))
+                }
+                ParseStep::Error(err) => (Response::new(error_status(&err)), None),
+            };
+            bytes = &[];
+
+            try!(stream.write_all(&frame_response(response).to_bytes()));
+
+            // A read timeout of zero isn't allowed, and `timeout=0` means not to wait anyway
+            match keep_alive {
+                Some(keep_alive) if keep_alive == timeout => {}
+                Some(keep_alive) if keep_alive > Duration::from_secs(0) => {
+                    try!(stream.set_read_timeout(Some(keep_alive)));
+                    idle_timeout = true;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+fn error_status(err: &ParserError) -> StatusCode {
+    match *err {
+        ParserError::BodyTooLarge => StatusCode::PayloadTooLarge,
+        ParserError::RequestLineTooLong => StatusCode::UriTooLong,
+        ParserError::HeadersTooLarge => StatusCode::RequestHeaderFieldsTooLarge,
+        _ => StatusCode::BadRequest,
+    }
+}
+
+// Makes sure the client can tell where the response ends on a persistent connection
+fn frame_response(mut response: Response) -> Response {
+    let code = response.status.as_u16();
+    let has_body = code >= 200 && code != 204 && code != 304;
+
+    if has_body && response.header("content-length").is_none() {
+        let length = response.body.len().to_string();
+        response.set_header("Content-Length", &length);
+    }
+
+    response
+}
@@ -0,0 +1,75 @@
+use request::Request;
+
+impl<'a> Request<'a> {
+    /// The canonical form of the request that signing schemes like AWS Signature Version 4
+    /// compute their HMAC over, one part per line:
+    ///
+    /// - the method
+    /// - the path, `/` if it's empty
+    /// - the query parameters sorted by name and then value, a parameter without a value
+    ///   getting an empty one (`a=`)
+    /// - a `name:value` line for each signed header in the order given, with the name
+    ///   lowercased and the value trimmed and its runs of whitespace collapsed to a space
+    /// - an empty line, then the lowercased names of the signed headers joined with `;`
+    ///
+    /// Parameters and paths are used as sent, without decoding, so the client has to have
+    /// signed them in that same encoding.
+    pub fn canonical_string(&self, signed_headers: &[&str]) -> String {
+        let path = match self.path() {
+            "" => "/",
+            path => path,
+        };
+
+        let mut pairs = self.query()
+                            .unwrap_or("")
+                            .split('&')
+                            .filter(|pair| !pair.is_empty())
+                            .map(|pair| {
+                                let mut parts = pair.splitn(2, '=');
+                                (parts.next().unwrap_or(""), parts.next().unwrap_or(""))
+                            })
+                            .collect::<Vec<_>>();
+        // Sorting the joined `name=value` strings would put `a-b=1` before `a=1`
+        pairs.sort();
+        let params = pairs.iter()
+                          .map(|&(name, value)| format!("{}={}", name, value))
+                          .collect::<Vec<_>>();
+
+        let names = signed_headers.iter().map(|name| name.to_lowercase()).collect::<Vec<_>>();
+
+        let mut canonical = format!("{}\n{}\n{}\n", self.method.as_str(), path, params.join("&"));
+        for name in &names {
+            let value = self.combined_header(name).unwrap_or_default();
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            canonical.push_str(&format!("{}:{}\n", name, value));
+        }
+        canonical.push_str(&format!("\n{}", names.join(";")));
+
+        canonical
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_request() {
+        let request = Request::from_str("GET /photos/list?size=large&album=2014&tag=b&tag=a&raw \
+                                         HTTP/1.1\r\nHost: Example.com\r\nX-Amz-Date:  \
+                                         20150830T123600Z \r\nX-Note: two   words\r\nX-Ignored: \
+                                         1\r\n\r\n")
+                          .unwrap();
+
+        assert_eq!(request.canonical_string(&["Host", "x-amz-date", "X-Note"]),
+                   "GET\n/photos/list\nalbum=2014&raw=&size=large&tag=a&tag=b\nhost:Example.com\n\
+                    x-amz-date:20150830T123600Z\nx-note:two words\n\nhost;x-amz-date;x-note");
+    }
+
+    #[test]
+    fn sort_params_by_name() {
+        let request = Request::from_str("GET /?a-b=1&a=2&a=1 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.canonical_string(&[]), "GET\n/\na=1&a=2&a-b=1\n\n");
+    }
+}
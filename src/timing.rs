@@ -0,0 +1,73 @@
+use headers::{parse_param, split_list_header, split_unquoted};
+use response::Response;
+
+/// One metric of a Server-Timing header, e.g. `db;dur=53.2;desc="Database"`
+#[derive(PartialEq, Debug, Clone)]
+pub struct ServerTiming {
+    pub name: String,
+    // In milliseconds
+    pub dur: Option<f64>,
+    pub desc: Option<String>,
+}
+
+impl Response {
+    /// The metrics of every Server-Timing header (W3C Server Timing) in order. A `dur`
+    /// that isn't a number is left out, like any parameter other than `dur` and `desc`.
+    pub fn server_timing(&self) -> Vec<ServerTiming> {
+        self.header_values("server-timing")
+            .into_iter()
+            .flat_map(split_list_header)
+            .filter_map(|metric| {
+                let mut parts = split_unquoted(metric, ';', false).into_iter();
+                let name = try_opt!(parts.next());
+                let mut timing = ServerTiming {
+                    name: name.to_string(),
+                    dur: None,
+                    desc: None,
+                };
+
+                // Only the first of a repeated parameter counts
+                for (name, value) in parts.map(parse_param) {
+                    match name.as_str() {
+                        "dur" if timing.dur.is_none() => timing.dur = value.parse().ok(),
+                        "desc" if timing.desc.is_none() => timing.desc = Some(value),
+                        _ => (),
+                    }
+                }
+
+                Some(timing)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::StatusCode;
+
+    #[test]
+    fn server_timing() {
+        let mut response = Response::new(StatusCode::Ok);
+        response.add_header("Server-Timing",
+                            "db;dur=53.2;desc=\"Database; primary\", app;dur=47.2");
+        response.add_header("Server-Timing", "cache;desc=miss");
+
+        assert_eq!(response.server_timing(),
+                   vec![ServerTiming {
+                            name: "db".to_string(),
+                            dur: Some(53.2),
+                            desc: Some("Database; primary".to_string()),
+                        },
+                        ServerTiming {
+                            name: "app".to_string(),
+                            dur: Some(47.2),
+                            desc: None,
+                        },
+                        ServerTiming {
+                            name: "cache".to_string(),
+                            dur: None,
+                            desc: Some("miss".to_string()),
+                        }]);
+    }
+}
@@ -0,0 +1,109 @@
+use request::Request;
+
+/// The W3C Trace Context of a request, the `traceparent` header split into its fields
+/// along with the `tracestate` vendors add to it
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct TraceContext<'a> {
+    pub version: u8,
+    // 32 lowercase hex digits
+    pub trace_id: &'a str,
+    // 16 lowercase hex digits, the id of the span that sent the request
+    pub parent_id: &'a str,
+    // Where bit 0 means the caller sampled (recorded) the trace
+    pub flags: u8,
+    pub tracestate: Option<&'a str>,
+}
+
+impl<'a> TraceContext<'a> {
+    pub fn is_sampled(&self) -> bool {
+        self.flags & 0x1 != 0
+    }
+}
+
+impl<'b> Request<'b> {
+    /// Parses the `traceparent` header, `None` if it's missing or malformed: fields of the
+    /// wrong length or not lowercase hex, the invalid version `ff`, or ids of only zeros.
+    /// Versions after 00 may add fields at the end, which are ignored.
+    pub fn trace_context(&self) -> Option<TraceContext<'_>> {
+        let traceparent = try_opt!(self.headers.get("traceparent")).trim();
+        let fields = traceparent.split('-').collect::<Vec<_>>();
+        if fields.len() < 4 {
+            return None;
+        }
+
+        let (version, trace_id, parent_id, flags) = (fields[0], fields[1], fields[2], fields[3]);
+        let is_hex = |field: &str, length: usize| {
+            field.len() == length &&
+            field.bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+        };
+        let is_zero = |field: &str| field.bytes().all(|byte| byte == b'0');
+
+        if !is_hex(version, 2) || version == "ff" || (version == "00" && fields.len() != 4) ||
+           !is_hex(trace_id, 32) || is_zero(trace_id) || !is_hex(parent_id, 16) ||
+           is_zero(parent_id) || !is_hex(flags, 2) {
+            return None;
+        }
+
+        Some(TraceContext {
+            version: try_opt!(u8::from_str_radix(version, 16).ok()),
+            trace_id: trace_id,
+            parent_id: parent_id,
+            flags: try_opt!(u8::from_str_radix(flags, 16).ok()),
+            tracestate: self.headers.get("tracestate").map(|value| value.trim()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace_context(traceparent: &str) -> Option<(u8, String, String, u8)> {
+        let text = format!("GET / HTTP/1.1\r\ntraceparent: {}\r\n\r\n", traceparent);
+        Request::from_str(&text).unwrap().trace_context().map(|context| {
+            (context.version,
+             context.trace_id.to_string(),
+             context.parent_id.to_string(),
+             context.flags)
+        })
+    }
+
+    #[test]
+    fn valid_traceparent() {
+        let text = "GET / HTTP/1.1\r\ntraceparent: \
+                    00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01\r\ntracestate: \
+                    rojo=00f067aa0ba902b7,congo=t61rcWkgMzE\r\n\r\n";
+        let request = Request::from_str(text).unwrap();
+        let context = request.trace_context().unwrap();
+
+        assert_eq!(context,
+                   TraceContext {
+                       version: 0,
+                       trace_id: "4bf92f3577b34da6a3ce929d0e0e4736",
+                       parent_id: "00f067aa0ba902b7",
+                       flags: 1,
+                       tracestate: Some("rojo=00f067aa0ba902b7,congo=t61rcWkgMzE"),
+                   });
+        assert!(context.is_sampled());
+
+        // A later version with an extra field
+        assert_eq!(trace_context("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-x")
+                       .map(|context| context.0),
+                   Some(1));
+    }
+
+    #[test]
+    fn invalid_traceparent() {
+        assert_eq!(trace_context("00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01"),
+                   None);
+        assert_eq!(trace_context("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"),
+                   None);
+        assert_eq!(trace_context("00-00000000000000000000000000000000-00f067aa0ba902b7-01"),
+                   None);
+        assert_eq!(trace_context("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+                   None);
+        assert_eq!(trace_context("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-x"),
+                   None);
+        assert_eq!(trace_context("garbage"), None);
+    }
+}
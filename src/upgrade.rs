@@ -0,0 +1,191 @@
+use base64::decode_base64url;
+use request::{is_absolute_form, ParserError, Request};
+use response::{Response, StatusCode};
+
+// Headers that are specific to an HTTP/1 connection and malformed in HTTP/2 (RFC 9113
+// section 8.2.2)
+const CONNECTION_SPECIFIC_HEADERS: [&'static str; 5] = ["connection",
+                                                        "keep-alive",
+                                                        "proxy-connection",
+                                                        "transfer-encoding",
+                                                        "upgrade"];
+
+// The only version of the WebSocket protocol there is, RFC 6455's
+const WEBSOCKET_VERSION: u32 = 13;
+
+impl<'a> Request<'a> {
+    /// Whether the client asks to switch the connection to cleartext HTTP/2 (RFC 7540
+    /// section 3.2): an Upgrade to `h2c`, a Connection header naming Upgrade and
+    /// HTTP2-Settings, and the HTTP2-Settings themselves
+    pub fn is_h2c_upgrade(&self) -> bool {
+        let connection = self.header_list("connection");
+        let names = |name: &str| connection.iter().any(|option| option.eq_ignore_ascii_case(name));
+
+        self.header_list("upgrade").iter().any(|protocol| protocol.eq_ignore_ascii_case("h2c")) &&
+        names("upgrade") && names("http2-settings") && self.http2_settings().is_some()
+    }
+
+    /// The payload of a SETTINGS frame the client sent base64url encoded in the
+    /// HTTP2-Settings header of an h2c upgrade, `None` if missing or not valid base64url
+    pub fn http2_settings(&self) -> Option<Vec<u8>> {
+        decode_base64url(try_opt!(self.headers.get("http2-settings")).trim())
+    }
+
+    /// The Sec-WebSocket-Version of a WebSocket handshake, `None` if missing or not a number
+    pub fn websocket_version(&self) -> Option<u32> {
+        try_opt!(self.headers.get("sec-websocket-version")).trim().parse().ok()
+    }
+
+    /// Checks that a WebSocket handshake is for version 13, the one this server speaks.
+    /// Otherwise the error is the `426 Upgrade Required` response to send back, telling
+    /// the client which version to retry with (RFC 6455 section 4.4).
+    pub fn check_websocket_version(&self) -> Result<(), Response> {
+        if self.websocket_version() == Some(WEBSOCKET_VERSION) {
+            return Ok(());
+        }
+
+        let mut response = Response::new(StatusCode::UpgradeRequired);
+        response.add_header("Sec-WebSocket-Version", &WEBSOCKET_VERSION.to_string());
+        Err(response)
+    }
+
+    /// The request's headers as HTTP/2 sends them: the pseudo-headers first (`:authority`
+    /// taking the place of Host), then the remaining headers sorted by name without the
+    /// connection-specific ones. `:scheme` is `http` unless the target is in absolute form,
+    /// a server behind TLS has to replace it.
+    ///
+    /// An upgrade request, or one whose Connection header names a header HTTP/2 can't do
+    /// without, is an `InvalidHeader` error, as dropping those would change what it means.
+    pub fn http2_headers(&self) -> Result<Vec<(String, String)>, ParserError> {
+        let named = self.header_list("connection")
+                        .iter()
+                        .map(|name| name.to_lowercase())
+                        .collect::<Vec<_>>();
+
+        if named.iter().any(|name| name == "upgrade") || self.headers.contains_key("upgrade") {
+            return Err(ParserError::InvalidHeader("upgrade".to_string()));
+        }
+        if named.iter().any(|name| name == "host") {
+            return Err(ParserError::InvalidHeader("host".to_string()));
+        }
+
+        let (scheme, authority, path) = if is_absolute_form(self.url) {
+            let mut parts = self.url.splitn(2, "://");
+            let scheme = parts.next().unwrap_or("");
+            let rest = parts.next().unwrap_or("");
+            let path_start = rest.find(|c| c == '/' || c == '?').unwrap_or(rest.len());
+            let path = if path_start == rest.len() || rest[path_start..].starts_with('?') {
+                format!("/{}", &rest[path_start..])
+            } else {
+                rest[path_start..].to_string()
+            };
+
+            (scheme.to_lowercase(), Some(rest[..path_start].to_string()), path)
+        } else {
+            ("http".to_string(), self.headers.get("host").cloned(), self.url.to_string())
+        };
+
+        let mut headers = vec![(":method".to_string(), self.method.as_str().to_string()),
+                               (":scheme".to_string(), scheme)];
+        if let Some(authority) = authority {
+            headers.push((":authority".to_string(), authority));
+        }
+        headers.push((":path".to_string(), path));
+
+        for (name, value) in self.headers_sorted() {
+            // TE is allowed, but only to say trailers are accepted. HTTP/1 requires naming
+            // it in Connection too, which doesn't make it any less allowed.
+            if name == "te" {
+                let te = self.header_list("te");
+                if te.iter().any(|coding| coding.eq_ignore_ascii_case("trailers")) {
+                    headers.push(("te".to_string(), "trailers".to_string()));
+                }
+                continue;
+            }
+
+            if name == "host" || CONNECTION_SPECIFIC_HEADERS.contains(&name) ||
+               named.iter().any(|named| named == name) {
+                continue;
+            }
+
+            headers.push((name.to_string(), value.to_string()));
+        }
+
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UPGRADE: &'static str = "GET / HTTP/1.1\r\nHost: server.example.com\r\nConnection: \
+                                   Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: \
+                                   AAMAAABkAARAAAAAAAIAAAAA\r\n\r\n";
+
+    #[test]
+    fn h2c_upgrade() {
+        let request = Request::from_str(UPGRADE).unwrap();
+
+        assert!(request.is_h2c_upgrade());
+        // SETTINGS_MAX_CONCURRENT_STREAMS = 100, SETTINGS_INITIAL_WINDOW_SIZE = 2^30 and
+        // SETTINGS_ENABLE_PUSH = 0, six bytes each
+        assert_eq!(request.http2_settings().unwrap(),
+                   vec![0, 3, 0, 0, 0, 100, 0, 4, 64, 0, 0, 0, 0, 2, 0, 0, 0, 0]);
+
+        let websocket = Request::from_str("GET / HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: \
+                                           websocket\r\n\r\n")
+                            .unwrap();
+        assert!(!websocket.is_h2c_upgrade());
+        assert_eq!(websocket.http2_settings(), None);
+    }
+
+    #[test]
+    fn websocket_version() {
+        let handshake = |version: &str| {
+            format!("GET /chat HTTP/1.1\r\nHost: example.com\r\nUpgrade: websocket\r\n\
+                     Connection: Upgrade\r\nSec-WebSocket-Version: {}\r\n\r\n",
+                    version)
+        };
+
+        let text = handshake("13");
+        let request = Request::from_str(&text).unwrap();
+        assert_eq!(request.websocket_version(), Some(13));
+        assert!(request.check_websocket_version().is_ok());
+
+        let text = handshake("8");
+        let request = Request::from_str(&text).unwrap();
+        assert_eq!(request.websocket_version(), Some(8));
+        let response = request.check_websocket_version().unwrap_err();
+        assert_eq!(response.status, StatusCode::UpgradeRequired);
+        assert_eq!(response.header("sec-websocket-version"), Some("13"));
+    }
+
+    #[test]
+    fn translate_to_http2() {
+        let request = Request::from_str("POST /submit?x=1 HTTP/1.1\r\nHost: example.com\r\n\
+                                         Connection: keep-alive, X-Hop, TE\r\nKeep-Alive: \
+                                         timeout=5\r\nX-Hop: 1\r\nTE: trailers, deflate\r\n\
+                                         Accept: */*\r\n\r\n")
+                          .unwrap();
+
+        let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(request.http2_headers().unwrap(),
+                   vec![pair(":method", "POST"),
+                        pair(":scheme", "http"),
+                        pair(":authority", "example.com"),
+                        pair(":path", "/submit?x=1"),
+                        pair("accept", "*/*"),
+                        pair("te", "trailers")]);
+
+        let request = Request::from_str("GET https://example.com:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(&request.http2_headers().unwrap()[1..],
+                   &[pair(":scheme", "https"), pair(":authority", "example.com:8443"),
+                     pair(":path", "/")]);
+
+        match Request::from_str(UPGRADE).unwrap().http2_headers() {
+            Err(ParserError::InvalidHeader(name)) => assert_eq!(name, "upgrade"),
+            other => panic!("expected InvalidHeader, got {:?}", other),
+        }
+    }
+}
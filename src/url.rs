@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use config::ParseConfig;
+use request::{ParserError, Request};
+
+/// Decodes `%XX` escapes. Malformed escapes are kept as they are, and byte sequences
+/// that don't decode to UTF-8 are replaced rather than failing the whole value.
+pub fn percent_decode(text: &str) -> String {
+    decode(text, false)
+}
+
+/// Like `percent_decode`, but also turns `+` into a space as
+/// `application/x-www-form-urlencoded` (and therefore query strings) require
+pub fn form_decode(text: &str) -> String {
+    decode(text, true)
+}
+
+fn decode(text: &str, plus_as_space: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                (Some(high), Some(low)) => Some(high * 16 + low),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(byte) = escaped {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(if plus_as_space && bytes[i] == b'+' {
+                b' '
+            } else {
+                bytes[i]
+            });
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+// The path of a request target, see `Request::path`
+pub(crate) fn target_path(url: &str) -> &str {
+    url.splitn(2, |c| c == '?' || c == '#').next().unwrap_or("")
+}
+
+// The query of a request target, see `Request::query`
+pub(crate) fn target_query(url: &str) -> Option<&str> {
+    url.splitn(2, '#').next().unwrap_or("").splitn(2, '?').nth(1)
+}
+
+impl<'a> Request<'a> {
+    /// The request target up to (but excluding) the query string or fragment
+    pub fn path(&self) -> &'a str {
+        target_path(self.url)
+    }
+
+    /// The raw query string without the leading `?`, if there is one
+    pub fn query(&self) -> Option<&'a str> {
+        target_query(self.url)
+    }
+
+    /// The `#fragment` of the target without the `#`. Clients aren't supposed to send one
+    /// and servers have to ignore it, but some do send it anyway.
+    pub fn fragment(&self) -> Option<&'a str> {
+        self.url.splitn(2, '#').nth(1)
+    }
+
+    /// The percent-decoded path. Unlike query values a `+` is kept as is, since it
+    /// only means space in form-encoded data.
+    pub fn decoded_path(&self) -> String {
+        percent_decode(self.path())
+    }
+
+    /// The path with runs of slashes collapsed into one, e.g. `/a/b/c` for `/a//b///c`.
+    /// This works on the raw path before percent-decoding, so an encoded `%2F` isn't a
+    /// separator and stays as it is.
+    pub fn collapsed_path(&self) -> String {
+        let mut collapsed = String::with_capacity(self.path().len());
+
+        for c in self.path().chars() {
+            if c != '/' || !collapsed.ends_with('/') {
+                collapsed.push(c);
+            }
+        }
+        collapsed
+    }
+
+    /// The path relative to where an app is mounted, e.g. `/users` for `/api/users` under
+    /// `/api`, and `/` for `/api` itself. `None` unless the prefix ends at a segment
+    /// boundary of the path, so `/apiv2` isn't under `/api`. A trailing slash on the
+    /// prefix doesn't matter.
+    pub fn strip_prefix(&self, prefix: &str) -> Option<&'a str> {
+        let prefix = prefix.trim_end_matches('/');
+        if !self.path().starts_with(prefix) {
+            return None;
+        }
+
+        match &self.path()[prefix.len()..] {
+            "" => Some("/"),
+            rest if rest.starts_with('/') => Some(rest),
+            _ => None,
+        }
+    }
+
+    /// The form-decoded query parameters. If a key is repeated the last value wins.
+    ///
+    /// There's no limit on the number of parameters here, as this can't fail and
+    /// `ParseConfig::max_query_params` is unset by default. A server decoding queries
+    /// from untrusted clients should use `query_params_with_config` with a limit instead.
+    pub fn query_params(&self) -> HashMap<String, String> {
+        self.query_pairs_raw()
+            .into_iter()
+            .map(|(key, value)| (form_decode(key), form_decode(value)))
+            .collect()
+    }
+
+    /// Like `query_params`, but a query with more parameters than
+    /// `ParseConfig::max_query_params` is `ParserError::TooManyParams`, found out before
+    /// decoding any of them
+    pub fn query_params_with_config(&self,
+                                    config: &ParseConfig)
+                                    -> Result<HashMap<String, String>, ParserError> {
+        Ok(try!(self.limited_query_pairs(config))
+               .into_iter()
+               .map(|(key, value)| (form_decode(key), form_decode(value)))
+               .collect())
+    }
+
+    /// The form-decoded query parameters with every value of a repeated key, in order.
+    /// Unlimited like `query_params`, see `query_params_multi_with_config` for a limit.
+    pub fn query_params_multi(&self) -> HashMap<String, Vec<String>> {
+        group_pairs(self.query_pairs_raw())
+    }
+
+    /// Like `query_params_multi`, limited like `query_params_with_config`
+    pub fn query_params_multi_with_config(&self,
+                                          config: &ParseConfig)
+                                          -> Result<HashMap<String, Vec<String>>, ParserError> {
+        Ok(group_pairs(try!(self.limited_query_pairs(config))))
+    }
+
+    // The raw pairs, unless there are more than the config allows. Stops counting at the
+    // first one too many, so a huge query isn't even split up completely.
+    fn limited_query_pairs(&self,
+                           config: &ParseConfig)
+                           -> Result<Vec<(&'a str, &'a str)>, ParserError> {
+        let max = match config.max_query_params {
+            Some(max) => max,
+            None => return Ok(self.query_pairs_raw()),
+        };
+
+        let pairs = self.query_pairs_iter().take(max + 1).collect::<Vec<_>>();
+        if pairs.len() > max {
+            return Err(ParserError::TooManyParams);
+        }
+        Ok(pairs)
+    }
+
+    /// The query parameters exactly as sent and in their order, without any decoding. A
+    /// key without a `=` has an empty value.
+    pub fn query_pairs_raw(&self) -> Vec<(&'a str, &'a str)> {
+        self.query_pairs_iter().collect()
+    }
+
+    fn query_pairs_iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.query()
+            .unwrap_or("")
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                (parts.next().unwrap_or(""), parts.next().unwrap_or(""))
+            })
+    }
+
+    /// Whether two requests ask for the same thing: the same method, decoded path, query
+    /// parameters in any order and headers in any order and name case. The version and
+    /// the body aren't compared.
+    pub fn semantically_equal(&self, other: &Request) -> bool {
+        self.method == other.method && self.decoded_path() == other.decoded_path() &&
+        self.sorted_query_pairs() == other.sorted_query_pairs() &&
+        self.headers == other.headers
+    }
+
+    // Unlike `query_params` this keeps repeated keys
+    fn sorted_query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = self.query_pairs_raw()
+                            .into_iter()
+                            .map(|(key, value)| (form_decode(key), form_decode(value)))
+                            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs
+    }
+}
+
+fn group_pairs(pairs: Vec<(&str, &str)>) -> HashMap<String, Vec<String>> {
+    let mut params = HashMap::<String, Vec<String>>::new();
+
+    for (key, value) in pairs {
+        params.entry(form_decode(key)).or_insert_with(Vec::new).push(form_decode(value));
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_escapes() {
+        assert_eq!(percent_decode("/a%20b%2Fc"), "/a b/c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(form_decode("a+b%2B"), "a b+");
+    }
+
+    #[test]
+    fn plus_in_query_but_not_path() {
+        let request = Request::from_str("GET /a+b?a=b+c&x%20y=1 HTTP/1.1\nHost: a\n\n").unwrap();
+
+        assert_eq!(request.path(), "/a+b");
+        assert_eq!(request.query(), Some("a=b+c&x%20y=1"));
+        assert_eq!(request.decoded_path(), "/a+b");
+
+        let params = request.query_params();
+        assert_eq!(params.get("a"), Some(&"b c".to_string()));
+        assert_eq!(params.get("x y"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn collapse_slashes() {
+        let request = Request::from_str("GET /a//b///c?x=//y HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.collapsed_path(), "/a/b/c");
+
+        let request = Request::from_str("GET //a/%2F/b/ HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.collapsed_path(), "/a/%2F/b/");
+    }
+
+    #[test]
+    fn strip_prefix() {
+        let request = Request::from_str("GET /api/users?page=2 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.strip_prefix("/api"), Some("/users"));
+        assert_eq!(request.strip_prefix("/api/"), Some("/users"));
+        assert_eq!(request.strip_prefix("/"), Some("/api/users"));
+        assert_eq!(request.strip_prefix("/api/users"), Some("/"));
+
+        let request = Request::from_str("GET /apiv2/users HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.strip_prefix("/api"), None);
+
+        assert_eq!(request.strip_prefix("/admin"), None);
+        assert_eq!(request.strip_prefix("/apiv2/users/more"), None);
+    }
+
+    #[test]
+    fn ignore_fragment() {
+        let request = Request::from_str("GET /page?x=1#section HTTP/1.1\n\n").unwrap();
+
+        assert_eq!(request.path(), "/page");
+        assert_eq!(request.query(), Some("x=1"));
+        assert_eq!(request.fragment(), Some("section"));
+
+        let request = Request::from_str("GET /page#a?b HTTP/1.1\n\n").unwrap();
+        assert_eq!(request.path(), "/page");
+        assert_eq!(request.query(), None);
+        assert_eq!(request.fragment(), Some("a?b"));
+    }
+
+    #[test]
+    fn semantically_equal() {
+        let request = Request::from_str("GET /a%20b?x=1&y=2 HTTP/1.1\r\nHost: a\r\nAccept: \
+                                         */*\r\n\r\n")
+                          .unwrap();
+        let reordered = Request::from_str("GET /%61%20b?y=2&x=1 HTTP/1.1\r\naccept: */*\r\nHOST: \
+                                           a\r\n\r\n")
+                            .unwrap();
+        let other_path = Request::from_str("GET /a?x=1&y=2 HTTP/1.1\r\nHost: a\r\nAccept: \
+                                            */*\r\n\r\n")
+                             .unwrap();
+
+        assert!(request.semantically_equal(&reordered));
+        assert!(!request.semantically_equal(&other_path));
+    }
+
+    #[test]
+    fn raw_query_pairs() {
+        let request = Request::from_str("GET /?b=x%20y&a=1&b=2&flag&=empty HTTP/1.1\n\n").unwrap();
+
+        assert_eq!(request.query_pairs_raw(),
+                   vec![("b", "x%20y"), ("a", "1"), ("b", "2"), ("flag", ""), ("", "empty")]);
+        assert!(Request::from_str("GET / HTTP/1.1\n\n").unwrap().query_pairs_raw().is_empty());
+    }
+
+    #[test]
+    fn many_query_params() {
+        let query = (0..1000).map(|i| format!("k{}=v+{}", i, i)).collect::<Vec<_>>().join("&");
+        let text = format!("GET /?{} HTTP/1.1\n\n", query);
+        let params = Request::from_str(&text).unwrap().query_params();
+
+        assert_eq!(params.len(), 1000);
+        for i in 0..1000 {
+            assert_eq!(params.get(&format!("k{}", i)), Some(&format!("v {}", i)));
+        }
+    }
+
+    #[test]
+    fn max_query_params() {
+        let request = Request::from_str("GET /?a=1&b=2&a=3 HTTP/1.1\n\n").unwrap();
+        let config = ParseConfig { max_query_params: Some(3), ..ParseConfig::default() };
+
+        assert_eq!(request.query_params_with_config(&config).unwrap().len(), 2);
+        assert_eq!(request.query_params_multi_with_config(&config).unwrap().get("a"),
+                   Some(&vec!["1".to_string(), "3".to_string()]));
+
+        let config = ParseConfig { max_query_params: Some(2), ..ParseConfig::default() };
+        match request.query_params_with_config(&config) {
+            Err(ParserError::TooManyParams) => (),
+            other => panic!("expected TooManyParams, got {:?}", other),
+        }
+        assert!(request.query_params_multi_with_config(&config).is_err());
+        // Without a config there's no limit
+        assert_eq!(request.query_params_multi().get("b"), Some(&vec!["2".to_string()]));
+    }
+}
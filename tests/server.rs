@@ -0,0 +1,116 @@
+extern crate http2;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use http2::config::ParseConfig;
+use http2::request::Method;
+use http2::response::{Response, StatusCode};
+use http2::server::Server;
+
+#[test]
+fn serve_request() {
+    let server = Server::bind("127.0.0.1:0")
+                     .unwrap()
+                     .route(Method::GET, "/hello/:name", |request| {
+                         let mut response = Response::new(StatusCode::Ok);
+                         let name = request.capture("/hello/:name").unwrap()["name"].clone();
+                         response.body = format!("hello {}", name).into_bytes();
+                         response
+                     });
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /hello/world HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+          .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert_eq!(response,
+               "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world");
+}
+
+fn request(addr: std::net::SocketAddr, text: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(text.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn survive_panicking_handler() {
+    let server = Server::bind("127.0.0.1:0")
+                     .unwrap()
+                     .threads(1)
+                     .route(Method::GET, "/panic", |_| panic!("handler failed"))
+                     .route(Method::GET, "/", |_| Response::new(StatusCode::NoContent));
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    // The connection is dropped, but the only worker lives on
+    assert_eq!(request(addr, "GET /panic HTTP/1.1\r\n\r\n"), "");
+    assert_eq!(request(addr, "GET / HTTP/1.1\r\nConnection: close\r\n\r\n"),
+               "HTTP/1.1 204 No Content\r\n\r\n");
+}
+
+#[test]
+fn close_idle_connection() {
+    let server = Server::bind("127.0.0.1:0")
+                     .unwrap()
+                     .threads(1)
+                     .timeout(Duration::from_millis(100))
+                     .route(Method::GET, "/", |_| Response::new(StatusCode::NoContent));
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    // Doesn't send anything, and would keep the only worker busy without a timeout
+    let mut idle = TcpStream::connect(addr).unwrap();
+    let mut response = String::new();
+    idle.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "");
+
+    assert_eq!(request(addr, "GET / HTTP/1.1\r\nConnection: close\r\n\r\n"),
+               "HTTP/1.1 204 No Content\r\n\r\n");
+}
+
+#[test]
+fn negotiated_keep_alive_timeout() {
+    let server = Server::bind("127.0.0.1:0")
+                     .unwrap()
+                     .route(Method::GET, "/", |_| Response::new(StatusCode::NoContent));
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    // The server would wait 30 seconds, the client only wants it to wait one
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\nKeep-Alive: timeout=1\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "HTTP/1.1 204 No Content\r\n\r\n");
+}
+
+#[test]
+fn limit_request_size() {
+    let server = Server::bind("127.0.0.1:0")
+                     .unwrap()
+                     .config(ParseConfig { max_total_bytes: Some(64), ..ParseConfig::default() })
+                     .route(Method::POST, "/", |_| Response::new(StatusCode::NoContent));
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    let response = request(addr, "POST / HTTP/1.1\r\nContent-Length: 1000\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"), "{}", response);
+}